@@ -0,0 +1,174 @@
+use crate::types::{Geometry, KeyboardLayout, LAYOUTS};
+use crate::utils::{base_key, get_keyboard_layout};
+
+use std::collections::HashMap;
+
+pub const FINGERS: [&str; 8] = ["LP", "LR", "LM", "LI", "RI", "RM", "RR", "RP"];
+
+const HOME_ROW: usize = 1;
+
+pub struct LayoutStats {
+    pub layout: KeyboardLayout,
+    pub keys: HashMap<String, u64>,
+    pub finger_load: [u64; 8],
+    presses: u64,
+    home_row: u64,
+    bigrams: u64,
+    same_finger: u64,
+    alternations: u64,
+    rolls: u64,
+}
+
+impl LayoutStats {
+    fn percent(&self, count: u64, total: u64) -> f64 {
+        if total == 0 {
+            return 0.0;
+        }
+        count as f64 / total as f64 * 100.0
+    }
+
+    pub fn same_finger(&self) -> f64 {
+        self.percent(self.same_finger, self.bigrams)
+    }
+
+    pub fn home_row(&self) -> f64 {
+        self.percent(self.home_row, self.presses)
+    }
+
+    pub fn alternation(&self) -> f64 {
+        self.percent(self.alternations, self.bigrams)
+    }
+
+    pub fn rolls(&self) -> f64 {
+        self.percent(self.rolls, self.bigrams)
+    }
+
+    pub fn finger(&self, finger: usize) -> f64 {
+        self.percent(self.finger_load[finger], self.presses)
+    }
+}
+
+fn finger(column: usize) -> usize {
+    match column {
+        0..=3 => column,
+        4 => 3,
+        5 => 4,
+        _ => column - 2,
+    }
+}
+
+fn positions(layout: &KeyboardLayout) -> HashMap<String, (usize, usize)> {
+    let mut positions = HashMap::new();
+    for (row, keys) in layout.rows.iter().enumerate() {
+        for (column, key) in keys.iter().enumerate() {
+            positions.insert(key.clone(), (row, column));
+        }
+    }
+    positions
+}
+
+pub fn analyze(name: &str, corpus: &str) -> LayoutStats {
+    let layout = get_keyboard_layout(Some(&String::from(name)), Geometry::default(), false);
+    let positions = positions(&layout);
+
+    let mut stats = LayoutStats {
+        layout,
+        keys: HashMap::new(),
+        finger_load: [0; 8],
+        presses: 0,
+        home_row: 0,
+        bigrams: 0,
+        same_finger: 0,
+        alternations: 0,
+        rolls: 0,
+    };
+
+    let mut last: Option<(usize, usize)> = None;
+
+    for c in corpus.chars() {
+        let key = base_key(c);
+        let Some(&(row, column)) = positions.get(&key) else {
+            last = None;
+            continue;
+        };
+
+        stats.presses += 1;
+        *stats.keys.entry(key).or_insert(0) += 1;
+        stats.finger_load[finger(column)] += 1;
+        if row == HOME_ROW {
+            stats.home_row += 1;
+        }
+
+        if let Some((last_row, last_column)) = last {
+            if (last_row, last_column) != (row, column) {
+                stats.bigrams += 1;
+                let (a, b) = (finger(last_column), finger(column));
+                if a == b {
+                    stats.same_finger += 1;
+                } else if (a < 4) != (b < 4) {
+                    stats.alternations += 1;
+                } else {
+                    stats.rolls += 1;
+                }
+            }
+        }
+        last = Some((row, column));
+    }
+
+    stats
+}
+
+pub fn analyze_all(corpus: &str) -> Vec<LayoutStats> {
+    LAYOUTS.iter().map(|name| analyze(name, corpus)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_finger_bigrams() {
+        let stats = analyze("qwerty", "ded");
+        assert_eq!((stats.bigrams, stats.same_finger), (2, 2));
+        assert_eq!(stats.same_finger(), 100.0);
+        assert_eq!(stats.finger(2), 100.0);
+    }
+
+    #[test]
+    fn alternation_and_rolls() {
+        let stats = analyze("qwerty", "fjas");
+        assert_eq!(stats.bigrams, 3);
+        assert_eq!((stats.alternations, stats.rolls), (2, 1));
+        assert!((stats.alternation() - 200.0 / 3.0).abs() < 1e-9);
+        assert!((stats.rolls() - 100.0 / 3.0).abs() < 1e-9);
+        assert_eq!(stats.same_finger(), 0.0);
+    }
+
+    #[test]
+    fn repeats_and_gaps_are_not_bigrams() {
+        let stats = analyze("qwerty", "aa s");
+        assert_eq!(stats.presses, 3);
+        assert_eq!(stats.bigrams, 0);
+        assert_eq!(stats.alternation(), 0.0);
+        assert_eq!(stats.keys["a"], 2);
+    }
+
+    #[test]
+    fn inner_columns_go_to_the_index_fingers() {
+        let stats = analyze("qwerty", "tgYH");
+        assert_eq!(stats.finger_load, [0, 0, 0, 2, 2, 0, 0, 0]);
+        assert_eq!(stats.home_row(), 50.0);
+        // t-g and y-h each stay on one index finger; g-y crosses hands.
+        assert_eq!((stats.same_finger, stats.alternations), (2, 1));
+    }
+
+    #[test]
+    fn layouts_are_compared_on_the_same_corpus() {
+        let all = analyze_all("arst neio");
+        let names: Vec<&str> = all.iter().map(|stats| stats.layout.name.as_str()).collect();
+        assert_eq!(names, LAYOUTS);
+        let colemak = &all[1];
+        assert_eq!(colemak.home_row(), 100.0);
+        assert!(all[0].home_row() < 100.0);
+    }
+}
@@ -0,0 +1,142 @@
+use crate::types::Keypress;
+
+use serde::{Deserialize, Serialize};
+
+// Keystrokes closer together than this are not typed by hand.
+const MIN_INTERVAL: u128 = 10_000_000;
+// Share of sub-10ms intervals tolerated, to allow for key rollover.
+const MAX_FAST_SHARE: f64 = 0.05;
+// A median gap this short is well beyond 400 wpm.
+const MIN_MEDIAN_INTERVAL: u128 = 30_000_000;
+// Human rhythm varies by far more than this from key to key.
+const MIN_VARIATION: f64 = 0.05;
+// Too few intervals say nothing about a rhythm.
+const MIN_SAMPLES: usize = 20;
+// Races with fewer keystrokes than this can't be checked, and a single quick word would
+// otherwise top every board.
+pub const MIN_KEYSTROKES: usize = MIN_SAMPLES + 1;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Flag {
+    Paste,
+    InhumanTiming,
+    ZeroVariance,
+    TooShort,
+}
+
+impl Flag {
+    pub fn description(&self) -> &'static str {
+        match self {
+            Flag::Paste => "pasted input",
+            Flag::InhumanTiming => "inhuman keystroke timing",
+            Flag::ZeroVariance => "machine-regular rhythm",
+            Flag::TooShort => "too short to check",
+        }
+    }
+}
+
+pub fn describe(flags: &[Flag]) -> String {
+    flags
+        .iter()
+        .map(Flag::description)
+        .collect::<Vec<&str>>()
+        .join(", ")
+}
+
+fn intervals(keystrokes: &[Keypress]) -> Vec<u128> {
+    keystrokes
+        .windows(2)
+        .map(|pair| pair[1].time.saturating_sub(pair[0].time))
+        .collect()
+}
+
+// Flags raised by the keystroke timings alone.
+pub fn timing_flags(keystrokes: &[Keypress]) -> Vec<Flag> {
+    let mut intervals = intervals(keystrokes);
+    let mut flags = Vec::new();
+    if intervals.is_empty() {
+        return flags;
+    }
+
+    let fast = intervals.iter().filter(|&&gap| gap < MIN_INTERVAL).count();
+    let fast_share = fast as f64 / intervals.len() as f64;
+
+    intervals.sort_unstable();
+    let median = intervals[intervals.len() / 2];
+
+    if fast_share > MAX_FAST_SHARE
+        || (intervals.len() >= MIN_SAMPLES && median < MIN_MEDIAN_INTERVAL)
+    {
+        flags.push(Flag::InhumanTiming);
+    }
+
+    if intervals.len() >= MIN_SAMPLES {
+        let mean = intervals.iter().sum::<u128>() as f64 / intervals.len() as f64;
+        let variance = intervals
+            .iter()
+            .map(|&gap| (gap as f64 - mean).powi(2))
+            .sum::<f64>()
+            / intervals.len() as f64;
+        if mean > 0.0 && variance.sqrt() / mean < MIN_VARIATION {
+            flags.push(Flag::ZeroVariance);
+        }
+    }
+
+    flags
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MILLI: u128 = 1_000_000;
+
+    fn keystrokes(gaps: impl IntoIterator<Item = u128>) -> Vec<Keypress> {
+        let mut time = 0;
+        std::iter::once(0)
+            .chain(gaps)
+            .enumerate()
+            .map(|(position, gap)| {
+                time += gap;
+                Keypress {
+                    time,
+                    position,
+                    typed: Some('a'),
+                    correct: true,
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn human_rhythm_is_not_flagged() {
+        let gaps = (0..40).map(|i| (120 + i * 37 % 90) * MILLI);
+        assert!(timing_flags(&keystrokes(gaps)).is_empty());
+        assert!(timing_flags(&[]).is_empty());
+    }
+
+    #[test]
+    fn rollover_is_tolerated_but_bursts_are_not() {
+        let rollover = (0..40).map(|i| if i == 7 { 5 } else { 100 + i * 13 % 70 } * MILLI);
+        assert!(timing_flags(&keystrokes(rollover)).is_empty());
+
+        let bursts = (0..40).map(|i| if i % 4 == 0 { 2 } else { 100 + i * 13 % 70 } * MILLI);
+        assert_eq!(timing_flags(&keystrokes(bursts)), [Flag::InhumanTiming]);
+    }
+
+    #[test]
+    fn short_median_needs_enough_samples() {
+        let fast = |count| (0..count).map(|i| (20 + i % 5) * MILLI);
+        assert!(timing_flags(&keystrokes(fast(MIN_SAMPLES as u128 - 1))).is_empty());
+        assert_eq!(
+            timing_flags(&keystrokes(fast(MIN_SAMPLES as u128))),
+            [Flag::InhumanTiming]
+        );
+    }
+
+    #[test]
+    fn metronome_rhythm_is_flagged() {
+        let steady = (0..30).map(|i| (100 + i % 2) * MILLI);
+        assert_eq!(timing_flags(&keystrokes(steady)), [Flag::ZeroVariance]);
+    }
+}
@@ -0,0 +1,374 @@
+use crate::stats::Record;
+use crate::types::{Quote, QUOTES};
+
+use rand::distributions::WeightedIndex;
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+// Tags and language of each built-in quote, by name.
+const DETAILS: [(&str, &[&str], &str); 10] = [
+    ("Raising Smart Kids for Dummies", &["book", "advice"], "en"),
+    ("The Empire Strikes Back", &["film", "sci-fi"], "en"),
+    ("Dictionary", &["words"], "en"),
+    (
+        "The Legend of Zelda: The Wind Waker",
+        &["game", "fantasy"],
+        "en",
+    ),
+    (
+        "The Unbearable Lightness of Being",
+        &["book", "literature"],
+        "en",
+    ),
+    ("Her", &["film", "love"], "en"),
+    (
+        "Lock, Stock, and Two Smoking Barrels",
+        &["film", "dialogue"],
+        "en",
+    ),
+    ("Through the Fire and Flames", &["song"], "en"),
+    ("Seven Seas", &["song"], "en"),
+    ("Avengers: Infinity War", &["film", "sci-fi"], "en"),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Length {
+    Short,
+    Medium,
+    Long,
+}
+
+impl Length {
+    pub const ALL: [Length; 3] = [Length::Short, Length::Medium, Length::Long];
+
+    pub fn of(words: usize) -> Length {
+        match words {
+            0..=29 => Length::Short,
+            30..=59 => Length::Medium,
+            _ => Length::Long,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Length::Short => "short",
+            Length::Medium => "medium",
+            Length::Long => "long",
+        }
+    }
+}
+
+// A built-in quote along with what the browser filters and ranks it by.
+#[derive(Clone, Debug)]
+pub struct Entry {
+    pub name: &'static str,
+    pub text: &'static str,
+    pub tags: &'static [&'static str],
+    pub language: &'static str,
+    // The user's best unflagged wpm on this quote.
+    pub best: Option<f64>,
+}
+
+impl Entry {
+    pub fn words(&self) -> usize {
+        self.text.split_whitespace().count()
+    }
+
+    pub fn length(&self) -> Length {
+        Length::of(self.words())
+    }
+
+    pub fn quote(&self) -> Quote {
+        Quote::new(String::from(self.name), String::from(self.text))
+    }
+}
+
+pub fn entries(records: &[Record], user: &str) -> Vec<Entry> {
+    QUOTES
+        .iter()
+        .map(|&(name, text)| {
+            let (tags, language) = DETAILS
+                .iter()
+                .find(|(quote, _, _)| *quote == name)
+                .map_or((&[][..], "en"), |&(_, tags, language)| (tags, language));
+            let best = records
+                .iter()
+                .filter(|record| {
+                    record.user == user && record.quote == name && record.flags.is_empty()
+                })
+                .map(|record| record.wpm)
+                .reduce(f64::max);
+            Entry {
+                name,
+                text,
+                tags,
+                language,
+                best,
+            }
+        })
+        .collect()
+}
+
+pub fn tags() -> Vec<&'static str> {
+    let mut tags: Vec<&str> = DETAILS
+        .iter()
+        .flat_map(|(_, tags, _)| *tags)
+        .copied()
+        .collect();
+    tags.sort();
+    tags.dedup();
+    tags
+}
+
+pub fn languages() -> Vec<&'static str> {
+    let mut languages: Vec<&str> = DETAILS.iter().map(|(_, _, language)| *language).collect();
+    languages.sort();
+    languages.dedup();
+    languages
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Filter {
+    pub query: String,
+    pub length: Option<Length>,
+    pub tag: Option<&'static str>,
+    pub language: Option<&'static str>,
+}
+
+impl Filter {
+    // Entries passing every filter. With a query, name matches come first, then the tightest
+    // matches; a match in the text only counts if it is close to the query as typed.
+    pub fn apply<'a>(&self, entries: &'a [Entry]) -> Vec<&'a Entry> {
+        let mut matches: Vec<((bool, usize), &Entry)> = entries
+            .iter()
+            .filter(|entry| self.length.is_none_or(|length| entry.length() == length))
+            .filter(|entry| self.tag.is_none_or(|tag| entry.tags.contains(&tag)))
+            .filter(|entry| {
+                self.language
+                    .is_none_or(|language| entry.language == language)
+            })
+            .filter_map(|entry| {
+                let rank = fuzzy(&self.query, entry.name)
+                    .map(|spread| (false, spread))
+                    .or_else(|| {
+                        fuzzy(&self.query, entry.text)
+                            .filter(|spread| *spread <= self.query.chars().count())
+                            .map(|spread| (true, spread))
+                    })?;
+                Some((rank, entry))
+            })
+            .collect();
+        matches.sort_by_key(|(rank, _)| *rank);
+        matches.into_iter().map(|(_, entry)| entry).collect()
+    }
+}
+
+// Whether `query` appears in `text` in order, ignoring case, and if so the fewest extra
+// characters between its first and last matched character.
+pub fn fuzzy(query: &str, text: &str) -> Option<usize> {
+    let query: Vec<char> = query.chars().flat_map(char::to_lowercase).collect();
+    let text: Vec<char> = text.chars().flat_map(char::to_lowercase).collect();
+    let Some(&first) = query.first() else {
+        return Some(0);
+    };
+
+    (0..text.len())
+        .filter(|&start| text[start] == first)
+        .filter_map(|start| {
+            let mut wanted = query[1..].iter().peekable();
+            for (index, c) in text.iter().enumerate().skip(start + 1) {
+                if wanted.peek().is_none() {
+                    break;
+                }
+                if wanted.peek() == Some(&c) {
+                    wanted.next();
+                }
+                if wanted.peek().is_none() {
+                    return Some(index - start + 1 - query.len());
+                }
+            }
+            (query.len() == 1).then_some(0)
+        })
+        .min()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Pick {
+    Random,
+    // Only quotes the user has no clean race on.
+    Unplayed,
+    // Raced quotes, more likely the lower the best wpm, or any quote before the first race.
+    Worst,
+}
+
+pub fn pick<'a>(entries: &[&'a Entry], pick: Pick) -> Option<&'a Entry> {
+    let mut rng = rand::thread_rng();
+    match pick {
+        Pick::Random => entries.choose(&mut rng).copied(),
+        Pick::Unplayed => {
+            let unplayed: Vec<&Entry> = entries
+                .iter()
+                .filter(|entry| entry.best.is_none())
+                .copied()
+                .collect();
+            unplayed.choose(&mut rng).copied()
+        }
+        Pick::Worst => {
+            let played: Vec<(&Entry, f64)> = entries
+                .iter()
+                .filter_map(|entry| entry.best.map(|best| (*entry, best)))
+                .collect();
+            let weights = WeightedIndex::new(played.iter().map(|(_, best)| 1.0 / best.max(1.0)));
+            match weights {
+                Ok(weights) => Some(played[rng.sample(weights)].0),
+                Err(_) => entries.choose(&mut rng).copied(),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::anticheat::Flag;
+
+    fn record(user: &str, quote: &str, wpm: f64, flags: Vec<Flag>) -> Record {
+        Record {
+            user: String::from(user),
+            quote: String::from(quote),
+            layout: String::from("qwerty"),
+            timestamp: 0,
+            wpm,
+            raw: wpm,
+            accuracy: 100.0,
+            consistency: 90.0,
+            burst: wpm,
+            slowest_word: None,
+            reaction: 0.5,
+            time: 30.0,
+            flags,
+            keystrokes: Vec::new(),
+            chain: String::new(),
+            signature: String::new(),
+        }
+    }
+
+    fn names<'a>(entries: &[&'a Entry]) -> Vec<&'a str> {
+        entries.iter().map(|entry| entry.name).collect()
+    }
+
+    #[test]
+    fn fuzzy_counts_the_extra_characters() {
+        assert_eq!(fuzzy("her", "Her"), Some(0));
+        assert_eq!(fuzzy("SEAS", "seven seas"), Some(0));
+        assert_eq!(fuzzy("esb", "The Empire Strikes Back"), Some(1));
+        assert_eq!(fuzzy("zw", "Zelda: The Wind Waker"), Some(10));
+        assert_eq!(fuzzy("x", "xylophone"), Some(0));
+    }
+
+    #[test]
+    fn fuzzy_picks_the_tightest_window() {
+        assert_eq!(fuzzy("ab", "a...ab"), Some(0));
+        assert_eq!(fuzzy("ab", "a..b ab"), Some(0));
+        assert_eq!(fuzzy("abc", "a.b.c"), Some(2));
+    }
+
+    #[test]
+    fn fuzzy_needs_every_character_in_order() {
+        assert_eq!(fuzzy("ba", "ab"), None);
+        assert_eq!(fuzzy("aa", "a"), None);
+        assert_eq!(fuzzy("q", "Her"), None);
+        assert_eq!(fuzzy("", "anything"), Some(0));
+        assert_eq!(fuzzy("", ""), Some(0));
+    }
+
+    #[test]
+    fn bests_are_the_users_own_clean_races() {
+        let records = [
+            record("a", "Her", 60.0, Vec::new()),
+            record("a", "Her", 75.0, Vec::new()),
+            record("a", "Her", 200.0, vec![Flag::Paste]),
+            record("b", "Her", 120.0, Vec::new()),
+        ];
+
+        let entries = entries(&records, "a");
+        assert_eq!(entries.len(), QUOTES.len());
+        let her = entries.iter().find(|entry| entry.name == "Her").unwrap();
+        assert_eq!(her.best, Some(75.0));
+        assert_eq!(her.tags, ["film", "love"]);
+        assert!(entries
+            .iter()
+            .filter(|entry| entry.name != "Her")
+            .all(|entry| entry.best.is_none()));
+    }
+
+    #[test]
+    fn filters_narrow_by_tag_and_length() {
+        let entries = entries(&[], "a");
+
+        let songs = Filter {
+            tag: Some("song"),
+            ..Filter::default()
+        };
+        assert_eq!(
+            names(&songs.apply(&entries)),
+            ["Through the Fire and Flames", "Seven Seas"]
+        );
+
+        for length in Length::ALL {
+            let filter = Filter {
+                length: Some(length),
+                ..Filter::default()
+            };
+            assert!(filter
+                .apply(&entries)
+                .iter()
+                .all(|entry| entry.length() == length));
+        }
+    }
+
+    #[test]
+    fn name_matches_rank_above_text_matches() {
+        let entries = entries(&[], "a");
+        let filter = Filter {
+            query: String::from("seas"),
+            ..Filter::default()
+        };
+
+        let matches = filter.apply(&entries);
+        assert_eq!(matches[0].name, "Seven Seas");
+        assert!(matches[1..]
+            .iter()
+            .all(|entry| fuzzy("seas", entry.name).is_none()));
+    }
+
+    #[test]
+    fn unplayed_and_worst_picks_respect_bests() {
+        let records = [
+            record("a", "Her", 40.0, Vec::new()),
+            record("a", "Seven Seas", 90.0, Vec::new()),
+        ];
+        let entries = entries(&records, "a");
+        let all: Vec<&Entry> = entries.iter().collect();
+
+        for _ in 0..50 {
+            assert!(pick(&all, Pick::Unplayed).unwrap().best.is_none());
+            assert!(pick(&all, Pick::Worst).unwrap().best.is_some());
+        }
+        let played: Vec<&Entry> = all
+            .iter()
+            .filter(|entry| entry.best.is_some())
+            .copied()
+            .collect();
+        assert!(pick(&played, Pick::Unplayed).is_none());
+        assert!(pick(&[], Pick::Random).is_none());
+    }
+
+    #[test]
+    fn worst_picks_anything_before_the_first_race() {
+        let entries = entries(&[], "a");
+        let all: Vec<&Entry> = entries.iter().collect();
+
+        assert!(pick(&all, Pick::Worst).is_some());
+    }
+}
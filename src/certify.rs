@@ -0,0 +1,267 @@
+use crate::source::{random_words, WORDS_PER_MINUTE};
+use crate::stats::Record;
+use crate::types::{Race, QUOTES};
+use crate::utils::date;
+
+pub const DURATIONS: [u64; 3] = [1, 3, 5];
+
+const VERIFICATION: &str = "The verification code is the signature of the test's record in the \
+                            stats file, which typing verify checks against its keystroke log.";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Penalty {
+    // Every wrong keystroke is one error.
+    Keystroke,
+    // Every word containing a wrong keystroke is one error.
+    #[default]
+    Word,
+}
+
+impl Penalty {
+    pub fn from_name(name: &str) -> Option<Penalty> {
+        match name {
+            "keystroke" => Some(Penalty::Keystroke),
+            "word" => Some(Penalty::Word),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Penalty::Keystroke => "keystroke",
+            Penalty::Word => "word",
+        }
+    }
+
+    pub fn errors(&self, race: &Race) -> u64 {
+        match self {
+            Penalty::Keystroke => race.incorrect(),
+            Penalty::Word => race.mistakes().len() as u64,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Format {
+    #[default]
+    Text,
+    Html,
+}
+
+impl Format {
+    pub fn from_name(name: &str) -> Option<Format> {
+        match name {
+            "text" | "txt" => Some(Format::Text),
+            "html" => Some(Format::Html),
+            _ => None,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Text => "txt",
+            Format::Html => "html",
+        }
+    }
+}
+
+// Fresh test text: words drawn at random from the whole quote corpus.
+pub fn test_text(minutes: u64) -> String {
+    let corpus = QUOTES.map(|(_, text)| text).join(" ");
+    random_words(
+        &corpus,
+        (minutes * WORDS_PER_MINUTE) as usize,
+        &mut rand::thread_rng(),
+    )
+}
+
+pub struct Certificate {
+    pub user: String,
+    pub layout: String,
+    pub minutes: u64,
+    pub timestamp: u64,
+    pub gross: f64,
+    pub net: f64,
+    pub accuracy: f64,
+    pub errors: u64,
+    pub penalty: Penalty,
+    // The signature of the race's record, which ties the certificate to a keystroke log that
+    // `typing verify` can check.
+    pub signature: String,
+}
+
+impl Certificate {
+    pub fn new(race: &Race, record: &Record, minutes: u64, penalty: Penalty) -> Certificate {
+        let errors = penalty.errors(race);
+        let gross = race.raw();
+        let net = (gross - errors as f64 / race.minutes()).max(0.0);
+
+        Certificate {
+            user: record.user.clone(),
+            layout: record.layout.clone(),
+            minutes,
+            timestamp: record.timestamp,
+            gross,
+            net,
+            accuracy: race.accuracy(),
+            errors,
+            penalty,
+            signature: record.signature.clone(),
+        }
+    }
+
+    fn rows(&self) -> [(&'static str, String); 9] {
+        [
+            ("Name", self.user.clone()),
+            ("Date", date(self.timestamp)),
+            ("Layout", self.layout.clone()),
+            ("Duration", format!("{} minute test", self.minutes)),
+            ("Net speed", format!("{:.0} wpm", self.net)),
+            ("Gross speed", format!("{:.0} wpm", self.gross)),
+            ("Accuracy", format!("{:.2}%", self.accuracy)),
+            (
+                "Errors",
+                format!("{} ({} penalty)", self.errors, self.penalty.name()),
+            ),
+            (
+                "Verification",
+                match self.signature.as_str() {
+                    "" => String::from("unsigned"),
+                    signature => String::from(signature),
+                },
+            ),
+        ]
+    }
+
+    pub fn render(&self, format: Format) -> String {
+        match format {
+            Format::Text => {
+                let mut text = String::from("TYPING CERTIFICATE\n\n");
+                for (name, value) in self.rows() {
+                    text.push_str(&format!("{name:<14}{value}\n"));
+                }
+                text.push_str("\nNet speed is gross wpm minus errors per minute.\n");
+                text.push_str(&format!("{VERIFICATION}\n"));
+                text
+            }
+            Format::Html => {
+                let rows: String = self
+                    .rows()
+                    .iter()
+                    .map(|(name, value)| {
+                        format!("<tr><th>{name}</th><td>{}</td></tr>\n", escape(value))
+                    })
+                    .collect();
+                format!(
+                    "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+                     <title>Typing certificate</title>\n<style>\n\
+                     body {{ font-family: serif; margin: 3em auto; max-width: 40em; }}\n\
+                     h1 {{ text-align: center; }}\n\
+                     th {{ text-align: left; padding-right: 2em; }}\n\
+                     td {{ font-family: monospace; word-break: break-all; }}\n\
+                     </style>\n</head>\n<body>\n<h1>Typing Certificate</h1>\n\
+                     <table>\n{rows}</table>\n\
+                     <p>Net speed is gross wpm minus errors per minute.</p>\n\
+                     <p>{VERIFICATION}</p>\n\
+                     </body>\n</html>\n"
+                )
+            }
+        }
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::RaceEngine;
+    use std::time::{Duration, Instant};
+
+    const TEXT: &str = "the quick brown fox jumps over the lazy dog and then runs far away";
+
+    // A one minute test with three wrong keystrokes: one in "quick" and two in "lazy".
+    fn certificate(penalty: Penalty) -> Certificate {
+        let start = Instant::now();
+        let mut engine = RaceEngine::new(TEXT, start).with_limit(Some(60_000_000_000));
+        let mut time = start;
+        let mut type_key = |engine: &mut RaceEngine, c| {
+            engine.input(Some(c), time);
+            time += Duration::from_millis(300 + engine.position() as u64 * 37 % 200);
+        };
+
+        for (i, c) in TEXT.chars().enumerate() {
+            if i == 5 || i == 36 || i == 37 {
+                type_key(&mut engine, '#');
+            }
+            type_key(&mut engine, c);
+        }
+
+        let race = engine.finish(start + Duration::from_secs(60));
+        let record = Record::new(&race, "user", "test", "qwerty", None);
+        Certificate::new(&race, &record, 1, penalty)
+    }
+
+    #[test]
+    fn keystroke_penalty_counts_every_wrong_key() {
+        let certificate = certificate(Penalty::Keystroke);
+        let gross = (TEXT.len() + 3) as f64 / 5.0;
+        assert_eq!(certificate.errors, 3);
+        assert!((certificate.gross - gross).abs() < 1e-9);
+        assert!((certificate.net - (gross - 3.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn word_penalty_counts_each_wrong_word_once() {
+        let certificate = certificate(Penalty::Word);
+        let gross = (TEXT.len() + 3) as f64 / 5.0;
+        assert_eq!(certificate.errors, 2);
+        assert!((certificate.net - (gross - 2.0)).abs() < 1e-9);
+        assert!(
+            (certificate.accuracy - TEXT.len() as f64 / (TEXT.len() + 3) as f64 * 100.0).abs()
+                < 1e-9
+        );
+    }
+
+    #[test]
+    fn net_speed_never_goes_negative() {
+        let start = Instant::now();
+        let mut engine = RaceEngine::new("ab", start);
+        for i in 0..30 {
+            engine.input(Some('#'), start + Duration::from_millis(200 * i));
+        }
+        engine.input(Some('a'), start + Duration::from_secs(6));
+        engine.input(Some('b'), start + Duration::from_secs(12));
+        let race = engine.finish(start + Duration::from_secs(12));
+        let record = Record::new(&race, "user", "test", "qwerty", None);
+
+        let certificate = Certificate::new(&race, &record, 1, Penalty::Keystroke);
+        assert_eq!(certificate.net, 0.0);
+        assert!(certificate.gross > 0.0);
+    }
+
+    #[test]
+    fn certificates_carry_the_record_signature() {
+        let start = Instant::now();
+        let mut engine = RaceEngine::new("ab", start);
+        engine.input(Some('a'), start);
+        engine.input(Some('b'), start + Duration::from_secs(1));
+        let race = engine.finish(start + Duration::from_secs(1));
+
+        let unsigned = Record::new(&race, "user", "test", "qwerty", None);
+        let rendered = Certificate::new(&race, &unsigned, 1, Penalty::Word).render(Format::Text);
+        assert!(rendered.contains("Verification  unsigned\n"));
+
+        let signed = Record::new(&race, "user", "test", "qwerty", Some(b"key"));
+        let certificate = Certificate::new(&race, &signed, 1, Penalty::Word);
+        assert_eq!(certificate.signature, signed.signature);
+        assert!(certificate
+            .render(Format::Html)
+            .contains(&format!("<td>{}</td>", signed.signature)));
+    }
+}
@@ -0,0 +1,185 @@
+use crate::certify::{Format, Penalty, DURATIONS};
+use crate::source::Mode;
+use crate::types::{Geometry, LAYOUTS};
+
+use clap::builder::{PossibleValuesParser, TypedValueParser};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
+use std::path::PathBuf;
+
+#[derive(Parser)]
+#[command(
+    name = "typing",
+    version,
+    about = "Typing practice in the terminal",
+    args_conflicts_with_subcommands = true
+)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    // The original form, `typing [username] [layout] [geometry] [theme]`, still starts a race.
+    #[arg(hide = true, num_args = 0..=4)]
+    pub legacy: Vec<String>,
+}
+
+impl Cli {
+    pub fn into_command(self) -> Command {
+        self.command.unwrap_or_else(|| {
+            let arg = |index: usize| self.legacy.get(index).cloned();
+            Command::Race(RaceArgs {
+                user: arg(0),
+                layout: arg(1),
+                geometry: arg(2).and_then(|name| Geometry::from_name(&name)),
+                theme: arg(3),
+                ..RaceArgs::default()
+            })
+        })
+    }
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Start racing (the default)
+    Race(RaceArgs),
+    /// Show personal bests, or the leaderboard when no user is given
+    Stats {
+        #[arg(short, long)]
+        user: Option<String>,
+    },
+    /// List recent races, newest first
+    History {
+        #[arg(short, long)]
+        user: Option<String>,
+        /// How many races to show
+        #[arg(short = 'n', long, default_value_t = 20)]
+        limit: usize,
+    },
+    /// Show a daily challenge leaderboard
+    Daily {
+        #[arg(long)]
+        team: Option<String>,
+        /// Which day, as YYYY-MM-DD; today when omitted
+        #[arg(long)]
+        date: Option<String>,
+    },
+    /// Write every recorded race to a file or stdout
+    Export {
+        #[arg(short, long, value_enum, default_value_t)]
+        format: ExportFormat,
+        /// Where to write, stdout when omitted
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Keyboard layouts
+    Layouts {
+        #[command(subcommand)]
+        command: List,
+    },
+    /// Built-in quotes
+    Quotes {
+        #[command(subcommand)]
+        command: List,
+    },
+    /// Compare layouts by typing effort over a corpus
+    AnalyzeLayout {
+        /// Text to analyze, the built-in quotes when omitted
+        file: Option<PathBuf>,
+        /// A built-in theme or the name of a theme file
+        #[arg(long)]
+        theme: Option<String>,
+    },
+    /// Take a timed certification test
+    Certify {
+        /// Test length in minutes: 1, 3 or 5
+        #[arg(value_parser = duration)]
+        minutes: u64,
+        #[arg(short, long)]
+        user: Option<String>,
+        #[arg(short, long, value_parser = PossibleValuesParser::new(LAYOUTS))]
+        layout: Option<String>,
+        /// Certificate file format
+        #[arg(short, long, value_parser = PossibleValuesParser::new(["text", "txt", "html"]).map(|name| Format::from_name(&name).unwrap_or_default()))]
+        format: Option<Format>,
+        /// What counts as one error: a wrong keystroke, or a word with any wrong keystroke
+        #[arg(short, long, value_parser = PossibleValuesParser::new(["keystroke", "word"]).map(|name| Penalty::from_name(&name).unwrap_or_default()))]
+        penalty: Option<Penalty>,
+    },
+    /// Check the signatures and keystroke logs of recorded races
+    Verify { file: Option<PathBuf> },
+    /// Print a shell completion script
+    Completions { shell: Shell },
+}
+
+#[derive(Subcommand)]
+pub enum List {
+    /// List them
+    List,
+}
+
+#[derive(Args, Default)]
+pub struct RaceArgs {
+    #[arg(short, long)]
+    pub user: Option<String>,
+    /// What to type; --time and --words imply their mode
+    #[arg(short, long, value_parser = PossibleValuesParser::new(["quote", "words", "time"]).map(|name| Mode::from_name(&name).unwrap_or_default()))]
+    pub mode: Option<Mode>,
+    #[arg(short, long, value_parser = PossibleValuesParser::new(LAYOUTS))]
+    pub layout: Option<String>,
+    #[arg(short, long, value_parser = PossibleValuesParser::new(["ansi", "iso", "ortho", "columnar", "split"]).map(|name| Geometry::from_name(&name).unwrap_or_default()))]
+    pub geometry: Option<Geometry>,
+    /// A built-in theme or the name of a theme file
+    #[arg(long)]
+    pub theme: Option<String>,
+    /// Race this quote, by name
+    #[arg(short, long, conflicts_with = "file")]
+    pub quote: Option<String>,
+    /// Race the text in this file, or draw words from it in words and time mode
+    #[arg(short, long)]
+    pub file: Option<PathBuf>,
+    /// Seconds per race in time mode
+    #[arg(short, long, value_parser = clap::value_parser!(u64).range(1..))]
+    pub time: Option<u64>,
+    /// Words per race in words mode
+    #[arg(short, long, value_parser = clap::value_parser!(u64).range(1..))]
+    pub words: Option<u64>,
+    /// Deal quotes in the order this seed gives, so a group can practice the same sequence
+    #[arg(long, conflicts_with = "daily")]
+    pub seed: Option<u64>,
+    /// Race today's shared text; only the first attempt is scored
+    #[arg(long, conflicts_with_all = ["quote", "file"])]
+    pub daily: bool,
+    /// Share a daily text with this team instead of everyone
+    #[arg(long, requires = "daily")]
+    pub team: Option<String>,
+    /// Milliseconds between the splits the results chart plots
+    #[arg(long, value_name = "MS", value_parser = clap::value_parser!(u64).range(1..))]
+    pub split: Option<u64>,
+    /// Seconds without input before a race pauses itself; 0 never pauses
+    #[arg(long, value_name = "SECS")]
+    pub afk: Option<u64>,
+    /// Announce progress and results as plain lines instead of drawing the screen
+    #[arg(long)]
+    pub screen_reader: bool,
+    /// Run a single race, then print its result as JSON once the terminal is restored
+    #[arg(long)]
+    pub json: bool,
+    /// Add the result of every finished race to this JSON file
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+}
+
+fn duration(minutes: &str) -> Result<u64, String> {
+    minutes
+        .parse()
+        .ok()
+        .filter(|minutes| DURATIONS.contains(minutes))
+        .ok_or_else(|| format!("certification tests last {DURATIONS:?} minutes"))
+}
+
+#[derive(Clone, Copy, Default, ValueEnum)]
+pub enum ExportFormat {
+    #[default]
+    Json,
+    Csv,
+}
@@ -0,0 +1,223 @@
+use crate::engine::Correction;
+use crate::source::{Mode, DEFAULT_TIME, DEFAULT_WORDS};
+use crate::types::{Hud, AFK_TIMEOUT, SPLIT_INTERVAL};
+use crate::utils::config_dir;
+
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+const CONFIG_FILE: &str = "config";
+const WORD_LISTS: &str = "words";
+
+// Defaults from <config>/config, which holds `key = value` lines like theme files do.
+// Anything left out or unreadable keeps its built-in default.
+#[derive(Clone, Debug)]
+pub struct Config {
+    pub username: Option<String>,
+    pub layout: Option<String>,
+    pub theme: Option<String>,
+    pub mode: Mode,
+    pub words: usize,
+    pub time: u64,
+    // File that words and time mode draw their words from.
+    pub word_list: Option<PathBuf>,
+    // Pace caret speed in wpm, 0 for none.
+    pub pacer: u64,
+    pub hud: Hud,
+    pub keyboard: bool,
+    pub correction: Correction,
+    // Milliseconds between the chart's splits.
+    pub split: u64,
+    // Seconds without input before a race pauses itself, 0 for never.
+    pub afk: u64,
+    // Plain line-based progress and results instead of the full-screen interface.
+    pub screen_reader: bool,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            username: None,
+            layout: None,
+            theme: None,
+            mode: Mode::default(),
+            words: DEFAULT_WORDS,
+            time: DEFAULT_TIME,
+            word_list: None,
+            pacer: 0,
+            hud: Hud::default(),
+            keyboard: true,
+            correction: Correction::default(),
+            split: (SPLIT_INTERVAL / 1_000_000) as u64,
+            afk: (AFK_TIMEOUT / 1_000_000_000) as u64,
+            screen_reader: false,
+        }
+    }
+}
+
+impl Config {
+    pub fn path() -> Option<PathBuf> {
+        config_dir().map(|dir| dir.join(CONFIG_FILE))
+    }
+
+    pub fn load() -> Config {
+        Config::path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map_or_else(Config::default, |contents| Config::parse(&contents))
+    }
+
+    pub fn parse(contents: &str) -> Config {
+        let mut config = Config::default();
+
+        for line in contents.lines() {
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let value = value.trim();
+            let text = (!value.is_empty()).then(|| String::from(value));
+
+            match key.trim() {
+                "username" => config.username = text,
+                "layout" => config.layout = text,
+                "theme" => config.theme = text,
+                "mode" => config.mode = Mode::from_name(value).unwrap_or(config.mode),
+                "words" => config.words = value.parse().unwrap_or(config.words),
+                "time" => config.time = value.parse().unwrap_or(config.time),
+                "word_list" => config.word_list = text.map(PathBuf::from),
+                "pacer" => config.pacer = value.parse().unwrap_or(config.pacer),
+                "hud" => config.hud = Hud::from_spec(value),
+                "keyboard" => config.keyboard = value.parse().unwrap_or(config.keyboard),
+                "correction" => {
+                    config.correction = Correction::from_name(value).unwrap_or(config.correction)
+                }
+                "split" => {
+                    config.split = value
+                        .parse()
+                        .ok()
+                        .filter(|ms| *ms > 0)
+                        .unwrap_or(config.split)
+                }
+                "afk" => config.afk = value.parse().unwrap_or(config.afk),
+                "screen_reader" => {
+                    config.screen_reader = value.parse().unwrap_or(config.screen_reader)
+                }
+                _ => (),
+            }
+        }
+
+        config
+    }
+
+    pub fn render(&self) -> String {
+        let text = |value: &Option<String>| value.clone().unwrap_or_default();
+        [
+            ("username", text(&self.username)),
+            ("layout", text(&self.layout)),
+            ("theme", text(&self.theme)),
+            ("mode", String::from(self.mode.name())),
+            ("words", self.words.to_string()),
+            ("time", self.time.to_string()),
+            (
+                "word_list",
+                self.word_list
+                    .as_ref()
+                    .map_or_else(String::new, |path| path.display().to_string()),
+            ),
+            ("pacer", self.pacer.to_string()),
+            ("hud", self.hud.spec()),
+            ("keyboard", self.keyboard.to_string()),
+            ("correction", String::from(self.correction.name())),
+            ("split", self.split.to_string()),
+            ("afk", self.afk.to_string()),
+            ("screen_reader", self.screen_reader.to_string()),
+        ]
+        .iter()
+        .map(|(key, value)| format!("{key} = {value}\n"))
+        .collect()
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let path = Config::path()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no config directory"))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, self.render())
+    }
+}
+
+// Word lists the settings screen offers, from <config>/words.
+pub fn word_lists() -> Vec<PathBuf> {
+    let mut lists: Vec<PathBuf> = config_dir()
+        .and_then(|dir| fs::read_dir(dir.join(WORD_LISTS)).ok())
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file())
+        .collect();
+    lists.sort();
+    lists
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rendered_configs_parse_back_the_same() {
+        let config = Config {
+            username: Some(String::from("ada")),
+            layout: Some(String::from("dvorak")),
+            theme: Some(String::from("solarized")),
+            mode: Mode::Time,
+            words: 40,
+            time: 60,
+            word_list: Some(PathBuf::from("/tmp/words.txt")),
+            pacer: 85,
+            hud: Hud::from_spec("wpm,progress"),
+            keyboard: false,
+            correction: Correction::Advance,
+            split: 250,
+            afk: 0,
+            screen_reader: true,
+        };
+
+        let parsed = Config::parse(&config.render());
+        assert_eq!(parsed.username.as_deref(), Some("ada"));
+        assert_eq!(parsed.mode, Mode::Time);
+        assert_eq!(parsed.word_list, config.word_list);
+        assert_eq!(parsed.hud.spec(), "wpm,progress");
+        assert_eq!(parsed.correction, Correction::Advance);
+        assert_eq!(parsed.split, 250);
+        assert_eq!(parsed.afk, 0);
+        assert!(parsed.screen_reader);
+        assert_eq!(parsed.render(), config.render());
+    }
+
+    #[test]
+    fn defaults_and_an_empty_hud_survive_a_round_trip() {
+        let config = Config {
+            hud: Hud::from_spec(""),
+            ..Config::default()
+        };
+
+        let parsed = Config::parse(&config.render());
+        assert!(parsed.username.is_none());
+        assert!(parsed.word_list.is_none());
+        assert!(parsed.hud.is_empty());
+        assert_eq!(parsed.render(), config.render());
+    }
+
+    #[test]
+    fn unreadable_values_keep_their_defaults() {
+        let config =
+            Config::parse("words = lots\nmode = marathon\nno equals sign\npacer=70\nsplit = 0\n");
+
+        assert_eq!(config.words, DEFAULT_WORDS);
+        assert_eq!(config.mode, Mode::Quote);
+        assert_eq!(config.pacer, 70);
+        assert!(config.keyboard);
+        assert_eq!(config.split, 1000);
+    }
+}
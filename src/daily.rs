@@ -0,0 +1,275 @@
+use crate::stats::Record;
+use crate::utils::date;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const DAILY_PATH: &str = "stats/daily.json";
+
+// One day's challenge. Days are UTC so a team spread over time zones shares the same text.
+#[derive(Clone, Debug)]
+pub struct Daily {
+    pub date: String,
+    // Lets a team race its own text instead of everyone's.
+    pub team: Option<String>,
+    // Whether the user's scored attempt is already used up.
+    pub attempted: bool,
+}
+
+impl Daily {
+    pub fn today(team: Option<String>) -> Daily {
+        Daily {
+            date: date(now())[..10].to_string(),
+            team,
+            attempted: false,
+        }
+    }
+
+    // Hashed rather than taken from a std hasher, whose output isn't promised to match across
+    // builds or machines.
+    pub fn seed(&self) -> u64 {
+        let digest = Sha256::digest(format!(
+            "{}:{}",
+            self.date,
+            self.team.as_deref().unwrap_or_default()
+        ));
+        digest[..8]
+            .iter()
+            .fold(0, |seed, byte| seed << 8 | u64::from(*byte))
+    }
+
+    pub fn name(&self) -> String {
+        match &self.team {
+            Some(team) => format!("daily {} ({team})", self.date),
+            None => format!("daily {}", self.date),
+        }
+    }
+
+    fn is_for(&self, entry: &Entry) -> bool {
+        entry.date == self.date && entry.team == self.team
+    }
+}
+
+// The scored attempt one user made on one day's challenge.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Entry {
+    pub date: String,
+    pub team: Option<String>,
+    pub user: String,
+    pub quote: String,
+    pub wpm: f64,
+    pub accuracy: f64,
+    pub timestamp: u64,
+    pub flagged: bool,
+    // Still false when the scored run was abandoned or restarted.
+    #[serde(default)]
+    pub finished: bool,
+}
+
+pub fn load(path: &Path) -> io::Result<Vec<Entry>> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(error) => return Err(error),
+    };
+
+    if contents.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    serde_json::from_str(&contents).map_err(io::Error::from)
+}
+
+pub fn has_attempt(entries: &[Entry], daily: &Daily, user: &str) -> bool {
+    entries
+        .iter()
+        .any(|entry| daily.is_for(entry) && entry.user == user)
+}
+
+// Takes the user's one scored attempt if it is still free. This happens when a run starts, so
+// restarting or quitting a run that is going badly doesn't earn another go. Returns whether the
+// run is scored.
+pub fn start(path: &Path, daily: &mut Daily, user: &str, quote: &str) -> io::Result<bool> {
+    let mut entries = load(path)?;
+    if has_attempt(&entries, daily, user) {
+        daily.attempted = true;
+        return Ok(false);
+    }
+
+    entries.push(Entry {
+        date: daily.date.clone(),
+        team: daily.team.clone(),
+        user: String::from(user),
+        quote: String::from(quote),
+        wpm: 0.0,
+        accuracy: 0.0,
+        timestamp: now(),
+        flagged: false,
+        finished: false,
+    });
+    save(path, &entries)?;
+    daily.attempted = true;
+    Ok(true)
+}
+
+// Fills in the scored attempt `start` took with the finished run.
+pub fn finish(path: &Path, daily: &Daily, record: &Record) -> io::Result<()> {
+    let mut entries = load(path)?;
+    let Some(entry) = entries
+        .iter_mut()
+        .find(|entry| daily.is_for(entry) && entry.user == record.user && !entry.finished)
+    else {
+        return Ok(());
+    };
+
+    entry.wpm = record.wpm;
+    entry.accuracy = record.accuracy;
+    entry.timestamp = record.timestamp;
+    entry.flagged = !record.flags.is_empty();
+    entry.finished = true;
+    save(path, &entries)
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs())
+}
+
+fn save(path: &Path, entries: &[Entry]) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, serde_json::to_string_pretty(entries)?)
+}
+
+// Finished, unflagged attempts at the day's challenge, fastest first.
+pub fn leaderboard<'a>(entries: &'a [Entry], daily: &Daily) -> Vec<&'a Entry> {
+    let mut board: Vec<&Entry> = entries
+        .iter()
+        .filter(|entry| daily.is_for(entry) && entry.finished && !entry.flagged)
+        .collect();
+    board.sort_by(|a, b| b.wpm.total_cmp(&a.wpm));
+    board
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::anticheat::Flag;
+    use std::env;
+    use std::path::PathBuf;
+    use std::process;
+
+    fn path(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("typing-daily-{name}-{}.json", process::id()))
+    }
+
+    fn record(user: &str, wpm: f64, flags: Vec<Flag>) -> Record {
+        Record {
+            user: String::from(user),
+            quote: String::from("daily"),
+            layout: String::from("qwerty"),
+            timestamp: 1,
+            wpm,
+            raw: wpm,
+            accuracy: 97.0,
+            consistency: 90.0,
+            burst: wpm,
+            slowest_word: None,
+            reaction: 0.5,
+            time: 30.0,
+            flags,
+            keystrokes: Vec::new(),
+            chain: String::new(),
+            signature: String::new(),
+        }
+    }
+
+    fn daily(date: &str, team: Option<&str>) -> Daily {
+        Daily {
+            date: String::from(date),
+            team: team.map(String::from),
+            attempted: false,
+        }
+    }
+
+    #[test]
+    fn seed_depends_on_date_and_team_only() {
+        let seed = daily("2026-10-19", None).seed();
+        assert_eq!(seed, daily("2026-10-19", None).seed());
+        assert_eq!(
+            seed,
+            Daily {
+                attempted: true,
+                ..daily("2026-10-19", None)
+            }
+            .seed()
+        );
+        assert_ne!(seed, daily("2026-10-20", None).seed());
+        assert_ne!(seed, daily("2026-10-19", Some("red")).seed());
+        assert_ne!(
+            daily("2026-10-19", Some("red")).seed(),
+            daily("2026-10-19", Some("blue")).seed()
+        );
+    }
+
+    #[test]
+    fn seed_is_stable_across_builds() {
+        // The first eight bytes of sha256("2026-10-19:"), so the day's text never moves.
+        let digest = Sha256::digest("2026-10-19:");
+        let expected = u64::from_be_bytes(digest[..8].try_into().unwrap());
+        assert_eq!(daily("2026-10-19", None).seed(), expected);
+    }
+
+    #[test]
+    fn each_user_gets_one_scored_attempt_per_challenge() {
+        let path = path("attempts");
+        let mut today = daily("2026-10-19", None);
+        let mut again = daily("2026-10-19", None);
+        let mut team = daily("2026-10-19", Some("core"));
+        let mut tomorrow = daily("2026-10-20", None);
+
+        let scored = [
+            start(&path, &mut today, "a", "daily").unwrap(),
+            start(&path, &mut again, "a", "daily").unwrap(),
+            start(&path, &mut again, "b", "daily").unwrap(),
+            start(&path, &mut team, "a", "daily").unwrap(),
+            start(&path, &mut tomorrow, "a", "daily").unwrap(),
+        ];
+        let entries = load(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(scored, [true, false, true, true, true]);
+        assert!(today.attempted && again.attempted);
+        assert_eq!(entries.unwrap().len(), 4);
+    }
+
+    #[test]
+    fn only_finished_clean_runs_reach_the_leaderboard() {
+        let path = path("board");
+        let mut today = daily("2026-10-19", None);
+        for user in ["a", "b", "c", "d"] {
+            start(&path, &mut today, user, "daily").unwrap();
+        }
+        finish(&path, &today, &record("a", 70.0, Vec::new())).unwrap();
+        finish(&path, &today, &record("b", 95.0, Vec::new())).unwrap();
+        finish(&path, &today, &record("c", 300.0, vec![Flag::Paste])).unwrap();
+        // A second finish doesn't replace the scored run.
+        finish(&path, &today, &record("a", 120.0, Vec::new())).unwrap();
+        let entries = load(&path);
+        fs::remove_file(&path).unwrap();
+
+        let entries = entries.unwrap();
+        let board = leaderboard(&entries, &today);
+        let scores: Vec<(&str, f64)> = board
+            .iter()
+            .map(|entry| (entry.user.as_str(), entry.wpm))
+            .collect();
+        assert_eq!(scores, [("b", 95.0), ("a", 70.0)]);
+        assert!(leaderboard(&entries, &daily("2026-10-19", Some("core"))).is_empty());
+    }
+}
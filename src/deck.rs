@@ -0,0 +1,189 @@
+use crate::utils::config_dir;
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+const DECKS_FILE: &str = "decks";
+
+#[derive(Clone, Debug)]
+enum Order {
+    // Shuffled at random, with each user's place kept in the decks file between runs.
+    Saved { user: String, path: PathBuf },
+    // Shuffled from a seed, so everyone using it is dealt the same sequence.
+    Seeded(Box<StdRng>),
+}
+
+// Deals card indices in shuffled rounds, each card once per round.
+#[derive(Clone, Debug)]
+pub struct Deck {
+    size: usize,
+    // Cards left this round, the next one last.
+    remaining: Vec<usize>,
+    last: Option<usize>,
+    order: Order,
+}
+
+impl Deck {
+    // Picks up where `user` left off in the decks file at `path`, or starts a fresh round.
+    pub fn load(path: PathBuf, user: &str, size: usize) -> Deck {
+        let saved = fs::read_to_string(&path).ok();
+        let mut deck = Deck {
+            size,
+            remaining: Vec::new(),
+            last: None,
+            order: Order::Saved {
+                user: String::from(user),
+                path,
+            },
+        };
+
+        let line = saved.as_deref().and_then(|contents| {
+            contents
+                .lines()
+                .filter_map(|line| line.split_once('='))
+                .find(|(name, _)| name.trim() == user)
+                .map(|(_, cards)| cards)
+        });
+        if let Some((last, remaining)) = line.and_then(|cards| cards.split_once('|')) {
+            deck.last = last.trim().parse().ok();
+            deck.remaining = remaining
+                .split_whitespace()
+                .filter_map(|card| card.parse().ok())
+                .filter(|card| *card < size)
+                .collect();
+        }
+        deck
+    }
+
+    pub fn seeded(seed: u64, size: usize) -> Deck {
+        Deck {
+            size,
+            remaining: Vec::new(),
+            last: None,
+            order: Order::Seeded(Box::new(StdRng::seed_from_u64(seed))),
+        }
+    }
+
+    pub fn deal(&mut self) -> usize {
+        if self.remaining.is_empty() {
+            self.shuffle();
+        }
+        let card = self.remaining.pop().unwrap_or_default();
+        self.last = Some(card);
+
+        // Losing the saved place only means a repeat sooner, so a failed save doesn't stop a race.
+        if let Order::Saved { user, path } = &self.order {
+            let _ = self.save(path, user);
+        }
+        card
+    }
+
+    fn shuffle(&mut self) {
+        self.remaining = (0..self.size).collect();
+        match &mut self.order {
+            Order::Saved { .. } => self.remaining.shuffle(&mut rand::thread_rng()),
+            Order::Seeded(rng) => self.remaining.shuffle(rng.as_mut()),
+        }
+
+        // Don't deal the card that ended the last round again straight away.
+        if self.size > 1 && self.remaining.last() == self.last.as_ref() {
+            self.remaining.swap(0, self.size - 1);
+        }
+    }
+
+    // <config>/decks, where a user's place is normally kept.
+    pub fn path() -> Option<PathBuf> {
+        config_dir().map(|dir| dir.join(DECKS_FILE))
+    }
+
+    fn save(&self, path: &Path, user: &str) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let cards: Vec<String> = self.remaining.iter().map(usize::to_string).collect();
+        let line = format!(
+            "{user} = {} | {}\n",
+            self.last.map_or_else(String::new, |last| last.to_string()),
+            cards.join(" ")
+        );
+        let others: String = fs::read_to_string(path)
+            .unwrap_or_default()
+            .lines()
+            .filter(|line| {
+                line.split_once('=')
+                    .is_none_or(|(name, _)| name.trim() != user)
+            })
+            .map(|line| format!("{line}\n"))
+            .collect();
+        fs::write(path, others + &line)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn deal(deck: &mut Deck, count: usize) -> Vec<usize> {
+        (0..count).map(|_| deck.deal()).collect()
+    }
+
+    #[test]
+    fn seeded_decks_deal_the_same_sequence() {
+        let first = deal(&mut Deck::seeded(7, 10), 50);
+        assert_eq!(first, deal(&mut Deck::seeded(7, 10), 50));
+        assert_ne!(first, deal(&mut Deck::seeded(8, 10), 50));
+    }
+
+    #[test]
+    fn every_card_is_dealt_once_per_round() {
+        let mut deck = Deck::seeded(1, 10);
+        for _ in 0..20 {
+            let mut round = deal(&mut deck, 10);
+            round.sort_unstable();
+            assert_eq!(round, (0..10).collect::<Vec<usize>>());
+        }
+    }
+
+    #[test]
+    fn no_card_is_dealt_twice_in_a_row() {
+        for seed in 0..50 {
+            let cards = deal(&mut Deck::seeded(seed, 3), 30);
+            assert!(cards.windows(2).all(|pair| pair[0] != pair[1]));
+        }
+        assert_eq!(deal(&mut Deck::seeded(0, 1), 3), [0, 0, 0]);
+    }
+
+    #[test]
+    fn dealing_goes_on_when_the_deck_cannot_be_saved() {
+        // Under a file rather than a directory, so the deck can be neither read nor written.
+        let mut deck = Deck::load(PathBuf::from("/dev/null/decks"), "user", 4);
+        let mut round = deal(&mut deck, 4);
+        round.sort_unstable();
+        assert_eq!(round, [0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn each_user_picks_up_where_they_left_off() {
+        let path = std::env::temp_dir().join(format!("typing-decks-{}", std::process::id()));
+        let mut first = Deck::load(path.clone(), "first", 10);
+        let mut second = Deck::load(path.clone(), "second", 10);
+        let dealt = deal(&mut first, 4);
+        deal(&mut second, 7);
+
+        let mut rest = deal(&mut Deck::load(path.clone(), "first", 10), 6);
+        let mut later = deal(&mut Deck::load(path.clone(), "second", 10), 3);
+        fs::remove_file(&path).unwrap();
+
+        rest.extend(dealt);
+        rest.sort_unstable();
+        assert_eq!(rest, (0..10).collect::<Vec<usize>>());
+        later.sort_unstable();
+        later.dedup();
+        assert_eq!(later.len(), 3);
+    }
+}
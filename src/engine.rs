@@ -0,0 +1,488 @@
+use crate::types::{Clock, Keypress, Race, Split, Status, AFK_TIMEOUT, SPLIT_INTERVAL};
+use crate::utils::base_key;
+
+use std::time::Instant;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CharState {
+    Pending,
+    Cursor,
+    Incorrect,
+    // Passed over with a wrong key.
+    Missed,
+    Correct,
+}
+
+// What a wrong key does to the cursor.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Correction {
+    // The cursor waits for the right key.
+    #[default]
+    Stop,
+    // The cursor moves on and the character stays wrong.
+    Advance,
+}
+
+impl Correction {
+    pub fn from_name(name: &str) -> Option<Correction> {
+        match name {
+            "stop" => Some(Correction::Stop),
+            "advance" => Some(Correction::Advance),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Correction::Stop => "stop",
+            Correction::Advance => "advance",
+        }
+    }
+}
+
+// Scoring for one race, driven entirely by the input and timestamps a front-end feeds it.
+#[derive(Clone, Debug)]
+pub struct RaceEngine {
+    text: Vec<char>,
+    states: Vec<CharState>,
+    position: usize,
+    hits: u64,
+    misses: u64,
+    clock: Clock,
+    interval: u128,
+    splits: Vec<Split>,
+    keystrokes: Vec<Keypress>,
+    pasted: bool,
+    shown: Instant,
+    last_input: Instant,
+    split_interval: u128,
+    afk_timeout: u128,
+    limit: Option<u128>,
+    correction: Correction,
+}
+
+impl RaceEngine {
+    // `shown` is when the text appeared, which reaction time is measured from.
+    pub fn new(text: &str, shown: Instant) -> RaceEngine {
+        let text: Vec<char> = text.chars().collect();
+        let mut states = vec![CharState::Pending; text.len()];
+        if let Some(first) = states.first_mut() {
+            *first = CharState::Cursor;
+        }
+
+        RaceEngine {
+            text,
+            states,
+            position: 0,
+            hits: 0,
+            misses: 0,
+            clock: Clock::default(),
+            interval: 0,
+            splits: Vec::new(),
+            keystrokes: Vec::new(),
+            pasted: false,
+            shown,
+            last_input: shown,
+            split_interval: SPLIT_INTERVAL,
+            afk_timeout: AFK_TIMEOUT,
+            limit: None,
+            correction: Correction::default(),
+        }
+    }
+
+    pub fn with_split_interval(mut self, split_interval: u128) -> RaceEngine {
+        self.split_interval = split_interval;
+        self
+    }
+
+    // Zero never pauses on idle.
+    pub fn with_afk_timeout(mut self, afk_timeout: u128) -> RaceEngine {
+        self.afk_timeout = afk_timeout;
+        self
+    }
+
+    // Ends the race once this many nanoseconds have been typed, text or not.
+    pub fn with_limit(mut self, limit: Option<u128>) -> RaceEngine {
+        self.limit = limit;
+        self
+    }
+
+    pub fn with_correction(mut self, correction: Correction) -> RaceEngine {
+        self.correction = correction;
+        self
+    }
+
+    pub fn text(&self) -> &[char] {
+        &self.text
+    }
+
+    pub fn states(&self) -> &[CharState] {
+        &self.states
+    }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn expected(&self) -> Option<char> {
+        self.text.get(self.position).copied()
+    }
+
+    pub fn is_started(&self) -> bool {
+        self.clock.started().is_some()
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.clock.is_paused()
+    }
+
+    pub fn elapsed(&self, now: Instant) -> u128 {
+        self.clock.elapsed_at(now)
+    }
+
+    fn timed_out(&self, now: Instant) -> bool {
+        self.limit.is_some_and(|limit| self.elapsed(now) >= limit)
+    }
+
+    pub fn is_finished(&self, now: Instant) -> bool {
+        self.position >= self.text.len() || self.timed_out(now)
+    }
+
+    // Any input at all keeps the race from idling. Scored keystrokes and pastes count on their
+    // own; this is for the rest, like a tab before a restart.
+    pub fn active(&mut self, at: Instant) {
+        self.last_input = at;
+    }
+
+    // Scores one keystroke against the expected character, returning whether it was right.
+    pub fn input(&mut self, typed: Option<char>, at: Instant) -> bool {
+        let Some(expected) = self.expected() else {
+            return false;
+        };
+        let correct = typed == Some(expected);
+
+        self.last_input = at;
+        self.clock.start_at(at);
+        self.keystrokes.push(Keypress {
+            time: self.clock.elapsed_at(at),
+            position: self.position,
+            typed,
+            correct,
+        });
+
+        if correct {
+            self.hits += 1;
+            self.advance(CharState::Correct);
+        } else {
+            self.misses += 1;
+            match self.correction {
+                Correction::Stop => self.states[self.position] = CharState::Incorrect,
+                Correction::Advance => self.advance(CharState::Missed),
+            }
+        }
+
+        correct
+    }
+
+    fn advance(&mut self, state: CharState) {
+        self.states[self.position] = state;
+        self.position += 1;
+        if let Some(next) = self.states.get_mut(self.position) {
+            *next = CharState::Cursor;
+        }
+    }
+
+    // Where a typist going at `wpm` would be by now.
+    pub fn pace(&self, now: Instant, wpm: u64) -> usize {
+        (self.elapsed(now) as f64 / 60e9 * wpm as f64 * 5.0) as usize
+    }
+
+    pub fn paste(&mut self, at: Instant) {
+        self.last_input = at;
+        self.pasted = true;
+    }
+
+    pub fn pause(&mut self, at: Instant) {
+        self.clock.pause(at);
+    }
+
+    pub fn resume(&mut self, at: Instant) {
+        self.clock.resume(at);
+    }
+
+    // Advances the timers, returning true when the race has just paused itself for idling.
+    pub fn tick(&mut self, now: Instant) -> bool {
+        let mut idled = false;
+        if self.afk_timeout > 0
+            && self.is_started()
+            && !self.is_paused()
+            && now.saturating_duration_since(self.last_input).as_nanos() >= self.afk_timeout
+        {
+            self.clock.pause(self.last_input);
+            let elapsed = self.elapsed(now);
+            self.splits.retain(|split| split.nanos() <= elapsed);
+            self.interval = self.splits.last().map_or(0, Split::nanos);
+            idled = true;
+        }
+
+        let elapsed = self.elapsed(now);
+        if self.is_started()
+            && elapsed.saturating_sub(self.interval) >= self.split_interval
+            && !self.timed_out(now)
+        {
+            self.splits
+                .push(Split::new(self.hits, self.misses, elapsed));
+            self.interval = elapsed;
+        }
+
+        idled
+    }
+
+    pub fn status(&self, now: Instant) -> Status {
+        let mut status = Status::new(
+            self.splits.last(),
+            self.hits,
+            self.misses,
+            self.elapsed(now),
+            self.position,
+            self.text.len(),
+            self.expected().map_or_else(String::new, base_key),
+        );
+        status.paused = self.is_paused();
+        status
+    }
+
+    pub fn finish(self, now: Instant) -> Race {
+        let elapsed = self.elapsed(now);
+        let end = self.limit.map_or(elapsed, |limit| elapsed.min(limit));
+        let reaction = self
+            .clock
+            .started()
+            .map_or(0, |start| start.duration_since(self.shown).as_nanos());
+
+        let mut race = Race::new(
+            self.hits,
+            self.misses,
+            end,
+            self.splits,
+            self.text.into_iter().collect(),
+            self.keystrokes,
+            reaction,
+        );
+        race.pasted = self.pasted;
+        race
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::anticheat::Flag;
+    use std::time::Duration;
+
+    const SECOND: u128 = 1_000_000_000;
+
+    fn at(start: Instant, millis: u64) -> Instant {
+        start + Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn stop_waits_for_the_right_key() {
+        let start = Instant::now();
+        let mut engine = RaceEngine::new("ab", start);
+
+        assert!(!engine.input(Some('x'), at(start, 100)));
+        assert_eq!(engine.position(), 0);
+        assert_eq!(engine.states()[0], CharState::Incorrect);
+
+        assert!(engine.input(Some('a'), at(start, 200)));
+        assert!(engine.input(Some('b'), at(start, 300)));
+        assert_eq!(engine.states(), [CharState::Correct, CharState::Correct]);
+
+        let race = engine.finish(at(start, 300));
+        assert_eq!(race.length, 2);
+        assert_eq!(race.incorrect(), 1);
+        assert_eq!(race.keystrokes.len(), 3);
+    }
+
+    #[test]
+    fn advance_moves_past_wrong_keys() {
+        let start = Instant::now();
+        let mut engine = RaceEngine::new("ab", start).with_correction(Correction::Advance);
+
+        assert!(!engine.input(Some('x'), at(start, 100)));
+        assert_eq!(engine.position(), 1);
+        assert_eq!(engine.states(), [CharState::Missed, CharState::Cursor]);
+
+        assert!(engine.input(Some('b'), at(start, 200)));
+        assert!(engine.is_finished(at(start, 200)));
+
+        let race = engine.finish(at(start, 200));
+        assert_eq!(race.length, 1);
+        assert_eq!(race.incorrect(), 1);
+    }
+
+    #[test]
+    fn characters_break_down_into_incorrect_extra_and_missed() {
+        let start = Instant::now();
+        let mut engine = RaceEngine::new("ab cd", start);
+        for (i, c) in "axbc cxd".chars().enumerate() {
+            engine.input(Some(c), at(start, 100 * i as u64));
+        }
+        let race = engine.finish(at(start, 800));
+        assert_eq!((race.incorrect(), race.extra(), race.missed()), (3, 1, 0));
+
+        let mut engine = RaceEngine::new("ab cd", start).with_correction(Correction::Advance);
+        for (i, c) in "ax xd".chars().enumerate() {
+            engine.input(Some(c), at(start, 100 * i as u64));
+        }
+        let race = engine.finish(at(start, 500));
+        assert_eq!(race.length, 3);
+        assert_eq!((race.incorrect(), race.extra(), race.missed()), (2, 0, 2));
+    }
+
+    #[test]
+    fn short_races_are_flagged() {
+        let start = Instant::now();
+        let race = |text: &str| {
+            let mut engine = RaceEngine::new(text, start);
+            for (i, c) in text.chars().enumerate() {
+                engine.input(Some(c), at(start, 20 + 150 * i as u64 + i as u64 * 37 % 60));
+            }
+            engine.finish(at(start, 150 * text.len() as u64 + 100))
+        };
+        assert_eq!(race("hi").flags(), [Flag::TooShort]);
+        assert!(race("a few more words to type").flags().is_empty());
+    }
+
+    #[test]
+    fn one_key_races_are_too_quick_to_score() {
+        let start = Instant::now();
+        let mut engine = RaceEngine::new("a", start);
+        engine.input(Some('a'), at(start, 300));
+        let race = engine.finish(at(start, 300));
+        assert_eq!(race.time, 0);
+        assert!(race.wpm().is_infinite());
+        assert!(!race.is_scorable(SECOND));
+        assert!(!race.is_scorable(0));
+
+        let mut engine = RaceEngine::new("ab", start);
+        engine.input(Some('a'), start);
+        engine.input(Some('b'), at(start, 1000));
+        assert!(engine.finish(at(start, 1000)).is_scorable(SECOND));
+    }
+
+    #[test]
+    fn splits_follow_the_interval() {
+        let start = Instant::now();
+        let mut engine = RaceEngine::new("abcdef", start)
+            .with_split_interval(SECOND)
+            .with_afk_timeout(0);
+
+        engine.tick(at(start, 500));
+        assert!(engine.clone().finish(at(start, 500)).splits.is_empty());
+
+        engine.input(Some('a'), start);
+        for millis in [500, 1000, 1500, 2000, 2500, 3200] {
+            engine.tick(at(start, millis));
+        }
+
+        let splits: Vec<u128> = engine
+            .finish(at(start, 3200))
+            .splits
+            .iter()
+            .map(Split::nanos)
+            .collect();
+        assert_eq!(splits, [SECOND, 2 * SECOND, 3200 * SECOND / 1000]);
+    }
+
+    #[test]
+    fn paused_time_is_not_counted() {
+        let start = Instant::now();
+        let mut engine = RaceEngine::new("abc", start).with_afk_timeout(0);
+
+        engine.input(Some('a'), start);
+        engine.pause(at(start, 1000));
+        assert!(engine.is_paused());
+        assert_eq!(engine.elapsed(at(start, 8000)), SECOND);
+
+        engine.resume(at(start, 11_000));
+        assert!(!engine.is_paused());
+        assert_eq!(engine.elapsed(at(start, 12_000)), 2 * SECOND);
+    }
+
+    #[test]
+    fn idling_pauses_from_the_last_input_and_drops_later_splits() {
+        let start = Instant::now();
+        let mut engine = RaceEngine::new("abcdef", start)
+            .with_split_interval(SECOND)
+            .with_afk_timeout(5 * SECOND);
+
+        engine.input(Some('a'), start);
+        engine.input(Some('b'), at(start, 2000));
+        for millis in [1000, 2000, 3000, 4000, 5000, 6000] {
+            assert!(!engine.tick(at(start, millis)));
+        }
+
+        assert!(engine.tick(at(start, 7000)));
+        assert!(engine.is_paused());
+        assert_eq!(engine.elapsed(at(start, 30_000)), 2 * SECOND);
+
+        let splits: Vec<u128> = engine
+            .finish(at(start, 30_000))
+            .splits
+            .iter()
+            .map(Split::nanos)
+            .collect();
+        assert_eq!(splits, [SECOND, 2 * SECOND]);
+    }
+
+    #[test]
+    fn typing_alone_keeps_a_long_race_from_idling() {
+        let start = Instant::now();
+        let text = "a".repeat(75);
+        let mut engine = RaceEngine::new(&text, start);
+
+        for i in 0..75 {
+            let now = at(start, 1000 + 200 * i);
+            engine.input(Some('a'), now);
+            assert!(!engine.tick(now));
+        }
+        assert!(!engine.is_paused());
+
+        let race = engine.finish(at(start, 1000 + 200 * 74));
+        assert_eq!(race.time, 74 * 200 * 1_000_000);
+        assert!(race.time > AFK_TIMEOUT);
+        assert_eq!(race.splits.len(), 14);
+    }
+
+    #[test]
+    fn idling_before_the_first_key_loses_no_time() {
+        let start = Instant::now();
+        let mut engine = RaceEngine::new("abc", start);
+
+        engine.input(Some('a'), at(start, 20_000));
+        assert!(!engine.tick(at(start, 20_500)));
+        assert!(engine.tick(at(start, 31_000)));
+        assert_eq!(engine.elapsed(at(start, 40_000)), 0);
+
+        engine.pause(start);
+        engine.resume(at(start, 40_000));
+        engine.input(Some('b'), at(start, 40_500));
+        assert_eq!(engine.elapsed(at(start, 41_000)), SECOND);
+    }
+
+    #[test]
+    fn limit_caps_the_race_time() {
+        let start = Instant::now();
+        let mut engine = RaceEngine::new("abcdef", start)
+            .with_afk_timeout(0)
+            .with_limit(Some(3 * SECOND));
+
+        engine.input(Some('a'), start);
+        assert!(!engine.is_finished(at(start, 2999)));
+        assert!(engine.is_finished(at(start, 3000)));
+
+        assert_eq!(engine.finish(at(start, 10_000)).time, 3 * SECOND);
+    }
+}
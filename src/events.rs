@@ -0,0 +1,68 @@
+use crossterm::event::{Event, EventStream, KeyEvent};
+use futures::StreamExt;
+use std::io;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tokio::time;
+
+// How often a running race redraws and checks its timers when no input arrives.
+pub const TICK: Duration = Duration::from_millis(50);
+
+#[derive(Clone, Debug)]
+pub enum AppEvent {
+    Key(KeyEvent, Instant),
+    Paste(Instant),
+    Resize,
+    Tick,
+}
+
+// Single queue that terminal input and any other event source feed into.
+pub struct Events {
+    receiver: UnboundedReceiver<AppEvent>,
+}
+
+impl Events {
+    pub fn new() -> Events {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        tokio::spawn(read_terminal(sender));
+        Events { receiver }
+    }
+
+    pub async fn next(&mut self) -> io::Result<AppEvent> {
+        self.receiver
+            .recv()
+            .await
+            .ok_or_else(|| io::Error::new(io::ErrorKind::BrokenPipe, "terminal input closed"))
+    }
+
+    // Waits for the next event, or yields a tick once `TICK` passes without one.
+    pub async fn next_or_tick(&mut self) -> io::Result<AppEvent> {
+        match time::timeout(TICK, self.next()).await {
+            Ok(event) => event,
+            Err(_) => Ok(AppEvent::Tick),
+        }
+    }
+}
+
+impl Default for Events {
+    fn default() -> Events {
+        Events::new()
+    }
+}
+
+// Runs on its own worker so events are stamped as they arrive, even while a frame is drawn.
+async fn read_terminal(sender: UnboundedSender<AppEvent>) {
+    let mut stream = EventStream::new();
+    while let Some(Ok(event)) = stream.next().await {
+        let now = Instant::now();
+        let event = match event {
+            Event::Key(key) => AppEvent::Key(key, now),
+            Event::Paste(_) => AppEvent::Paste(now),
+            Event::Resize(_, _) => AppEvent::Resize,
+            _ => continue,
+        };
+        if sender.send(event).is_err() {
+            break;
+        }
+    }
+}
@@ -0,0 +1,13 @@
+pub mod analyze;
+pub mod anticheat;
+pub mod catalog;
+pub mod certify;
+pub mod config;
+pub mod daily;
+pub mod deck;
+pub mod engine;
+pub mod source;
+pub mod stats;
+pub mod types;
+pub mod utils;
+pub mod verify;
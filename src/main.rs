@@ -1,287 +1,638 @@
-mod types;
+mod cli;
+mod events;
+mod reader;
+mod report;
+mod screens;
+mod theme;
 mod ui;
-mod utils;
 
+use typing::{
+    analyze, anticheat, catalog, certify, config, daily, deck, engine, source, stats, types, utils,
+    verify,
+};
+
+use certify::{Certificate, Format, Penalty};
+use clap::{error::ErrorKind, CommandFactory, Parser};
+use cli::{Cli, Command, RaceArgs};
+use config::Config;
 use crossterm::event::KeyModifiers;
+use daily::{Daily, DAILY_PATH};
+use deck::Deck;
+use engine::Correction;
+use events::{AppEvent, Events};
+use screens::{App, Outcome, RaceScreen, Screen, Title};
+use source::{find_quote, load_text, Mode, TextSource};
+use stats::{RaceResult, Record, STATS_PATH};
+use std::collections::HashMap;
 use std::env;
-use std::io::{self, stdout};
+use std::fs;
+use std::io::{self, stderr, stdout, Write};
+use std::path::{Path, PathBuf};
 use std::time::Instant;
-use types::{Keystroke, Race, Split, CORRECT, INCORRECT, TITLE};
-use utils::{get_keyboard_layout, get_quote};
+use theme::{Theme, THEMES};
+use types::{Geometry, Hud, KeyboardLayout, Keystroke, Quote, Race, Session, KEY_HEIGHT, QUOTES};
+use utils::get_keyboard_layout;
 
 use crossterm::{
-    event::{self, Event, KeyCode},
+    event::{self, DisableBracketedPaste, EnableBracketedPaste, KeyCode},
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
     ExecutableCommand,
 };
 
 use ratatui::{prelude::*, widgets::*};
 
-fn main() -> io::Result<()> {
-    enable_raw_mode()?;
-    stdout().execute(EnterAlternateScreen)?;
-    let mut terminal = Terminal::new(CrosstermBackend::new(stdout()))?;
+#[tokio::main]
+async fn main() -> io::Result<()> {
+    match Cli::parse().into_command() {
+        Command::Race(args) => race(args).await,
+        Command::Stats { user } => report::stats(Path::new(STATS_PATH), user.as_deref()),
+        Command::Daily { team, date } => report::daily(Path::new(DAILY_PATH), team, date),
+        Command::History { user, limit } => {
+            report::history(Path::new(STATS_PATH), user.as_deref(), limit)
+        }
+        Command::Export { format, output } => {
+            report::export(Path::new(STATS_PATH), format, output.as_deref())
+        }
+        Command::Layouts { .. } => {
+            report::layouts();
+            Ok(())
+        }
+        Command::Quotes { .. } => {
+            report::quotes();
+            Ok(())
+        }
+        Command::AnalyzeLayout { file, theme } => {
+            let corpus = match file {
+                Some(path) => fs::read_to_string(path)?,
+                None => QUOTES.map(|(_, text)| text).join(" "),
+            };
+            analyze_layout(&corpus, &Theme::load(theme.as_ref())).await
+        }
+        Command::Certify {
+            minutes,
+            user,
+            layout,
+            format,
+            penalty,
+        } => {
+            certify(
+                minutes,
+                user,
+                layout,
+                format.unwrap_or_default(),
+                penalty.unwrap_or_default(),
+            )
+            .await
+        }
+        Command::Verify { file } => {
+            verify_records(file.as_deref().unwrap_or(Path::new(STATS_PATH)))
+        }
+        Command::Completions { shell } => {
+            clap_complete::generate(shell, &mut Cli::command(), "typing", &mut stdout());
+            Ok(())
+        }
+    }
+}
+
+// Builds the text source a race command asks for, exiting with a usage error when it can't.
+// Flags override the config file.
+fn text_source(args: &RaceArgs, config: &Config, username: &str) -> io::Result<TextSource> {
+    let mode = if args.time.is_some() {
+        Mode::Time
+    } else if args.words.is_some() {
+        Mode::Words
+    } else {
+        config.mode
+    };
+    let mut source = TextSource {
+        mode: args.mode.unwrap_or(mode),
+        words: args.words.map_or(config.words, |words| words as usize),
+        time: args.time.unwrap_or(config.time),
+        deck: match args.seed {
+            Some(seed) => Some(Deck::seeded(seed, QUOTES.len())),
+            None => Deck::path().map(|path| Deck::load(path, username, QUOTES.len())),
+        },
+        ..TextSource::default()
+    };
+
+    if args.daily {
+        let mut today = Daily::today(args.team.clone());
+        today.attempted =
+            daily::has_attempt(&daily::load(Path::new(DAILY_PATH))?, &today, username);
+        source.daily = Some(today);
+    }
 
-    let args: Vec<String> = env::args().collect();
-
-    let mut total_words = 0.0;
-    let mut total_time = 0.0;
-    let mut last_race: Option<Race> = None;
-    let default_user = String::from("default");
-    let username = args.get(1).clone().unwrap_or(&default_user);
-    let layout = args.get(2).clone();
-
-    'game: loop {
-        let mut show_results = true;
-        'title: loop {
-            terminal.draw(|frame| {
-                ui::title(
-                    frame,
-                    last_race.clone(),
-                    total_words,
-                    total_time,
-                    username,
-                    show_results,
+    if let Some(name) = &args.quote {
+        let Some(quote) = find_quote(name) else {
+            Cli::command()
+                .error(
+                    ErrorKind::InvalidValue,
+                    format!("no quote named '{name}', see `typing quotes list`"),
                 )
-            })?;
-            if let Ok(c) = handle_events() {
-                if c == KeyCode::Char('s') {
-                    break 'title;
-                } else if c == KeyCode::Char('q') {
-                    break 'game;
-                } else if c == KeyCode::Char('r') {
-                    show_results = if show_results { false } else { true };
-                }
-            }
-        }
-        let mut hits = 0;
-        let mut misses = 0;
-        let mut set: usize = 0;
-
-        let quote = get_quote();
-        let (quote_name, quote_text) = (quote.get_name(), quote.get_text());
-
-        let mut position: usize = 0;
-        let line = Line::from(
-            quote_text
-                .chars()
-                .map(|c| Span::raw(c.to_string()))
-                .collect::<Vec<Span>>(),
-        );
-        let mut text = Text::from(line);
-
-        let mut start: Option<Instant> = None;
-        let mut interval = 0;
-        let mut splits: Vec<Split> = Vec::new();
-
-        'race: loop {
-            if let Some(time) = start {
-                let elapsed = time.elapsed().as_nanos();
-                if elapsed - interval >= 1e9 as u128 {
-                    splits.push(Split::new(hits, misses, elapsed));
-                    interval = elapsed;
-                }
-            }
+                .exit();
+        };
+        source.quote = Some(quote);
+    }
+
+    if let Some(path) = &args.file {
+        source.custom = Some(load_nonempty(path)?);
+    }
+    if let Some(path) = &config.word_list {
+        source.word_list = Some(load_nonempty(path)?);
+    }
 
-            let paragraph = Paragraph::new(text.clone());
-            let mut next_char = String::new();
+    Ok(source)
+}
 
-            if let Some(c) = quote_text.chars().nth(position) {
-                let c = match c.to_string().to_lowercase().as_str() {
-                    ":" => String::from(";"),
-                    "<" => String::from(","),
-                    ">" => String::from("."),
-                    "?" => String::from("/"),
-                    _ => c.to_string(),
-                };
+fn load_nonempty(path: &Path) -> io::Result<Quote> {
+    let text = load_text(path)?;
+    if text.get_text().is_empty() {
+        Cli::command()
+            .error(
+                ErrorKind::InvalidValue,
+                format!("{} is empty", path.display()),
+            )
+            .exit();
+    }
+    Ok(text)
+}
 
-                next_char = c.to_lowercase();
-            }
+async fn race(args: RaceArgs) -> io::Result<()> {
+    let config = Config::load();
+    let username = args
+        .user
+        .clone()
+        .or_else(|| config.username.clone())
+        .unwrap_or_else(|| String::from("default"));
+
+    let geometry = args.geometry.unwrap_or_default();
+    let theme_name = args.theme.as_ref().or(config.theme.as_ref());
+    let options = Options {
+        keyboard: get_keyboard_layout(
+            args.layout.as_ref().or(config.layout.as_ref()),
+            geometry,
+            false,
+        ),
+        geometry,
+        theme: Theme::load(theme_name),
+        theme_name: theme_name.map_or_else(|| String::from(THEMES[0]), String::clone),
+        hud: env::var("TYPING_HUD").map_or(config.hud, |spec| Hud::from_spec(&spec)),
+        show_keyboard: config.keyboard,
+        pacer: config.pacer,
+        correction: config.correction,
+        split_interval: u128::from(args.split.unwrap_or(config.split)) * 1_000_000,
+        afk_timeout: u128::from(args.afk.unwrap_or(config.afk)) * 1_000_000_000,
+        source: text_source(&args, &config, &username)?,
+        output: args.output.clone(),
+        once: args.json,
+    };
+
+    let session = Session {
+        history: stats::load(Path::new(STATS_PATH))?,
+        key: verify::install_key().ok(),
+        ..Session::default()
+    };
+
+    // With --json, stdout carries nothing but the result, so the race is drawn on stderr.
+    let mut output: Box<dyn Write> = if args.json {
+        Box::new(stderr())
+    } else {
+        Box::new(stdout())
+    };
+
+    if args.screen_reader || config.screen_reader {
+        enable_raw_mode()?;
+        output.execute(EnableBracketedPaste)?;
+        let result = reader::run(&username, session, &options, &mut Events::new()).await;
+        output.execute(DisableBracketedPaste)?;
+        disable_raw_mode()?;
+        return print_result(args.json, &result?);
+    }
 
-            terminal.draw(|frame| {
-                ui::race(
-                    frame,
-                    quote_name.clone(),
-                    paragraph.clone(),
-                    layout,
-                    next_char.clone(),
-                )
-            })?;
-
-            if let Some(c) = quote_text.chars().nth(position) {
-                if position == set {
-                    if let Some(span) = text.lines[0].spans.get_mut(position) {
-                        text.lines[0].spans[position] = span.clone().bg(TITLE);
-                        set += 1;
-                    }
-                }
+    let mut backend = CrosstermBackend::new(output);
+    enable_raw_mode()?;
+    backend.execute(EnterAlternateScreen)?;
+    backend.execute(EnableBracketedPaste)?;
+    let mut terminal = Terminal::new(backend)?;
+    let mut events = Events::new();
+
+    let mut app = App {
+        username,
+        session,
+        options,
+        config,
+    };
+    let first: Box<dyn Screen> = if app.options.once {
+        Box::new(RaceScreen::next(&mut app.options))
+    } else {
+        Box::new(Title)
+    };
+    // Restores the terminal before any error is reported.
+    let result = screens::run(&mut terminal, &mut events, &mut app, first).await;
 
-                if let Ok(b) = handle_race(c) {
-                    match b {
-                        Keystroke::Correct => {
-                            if start.is_none() {
-                                start = Some(Instant::now());
-                            }
-                            if let Some(span) = text.lines[0].spans.get_mut(position) {
-                                text.lines[0].spans[position] =
-                                    span.clone().bg(Color::Reset).fg(CORRECT);
-                            }
-                            position += 1;
-                            hits += 1;
-                        }
-                        Keystroke::Wrong => {
-                            if start.is_none() {
-                                start = Some(Instant::now());
-                            }
-                            if let Some(span) = text.lines[0].spans.get_mut(position) {
-                                text.lines[0].spans[position] = span.clone().bg(INCORRECT);
-                            }
-                            misses += 1;
-                        }
-                        Keystroke::Quit => {
-                            break 'race;
-                        }
-                        Keystroke::Invalid => (),
-                    }
-                }
-            } else {
-                let end = start.unwrap().elapsed().as_nanos();
+    disable_raw_mode()?;
+    terminal.backend_mut().execute(DisableBracketedPaste)?;
+    terminal.backend_mut().execute(LeaveAlternateScreen)?;
+    result?;
+    print_result(args.json, &app.session)
+}
 
-                let race = Race::new(hits, misses, end, splits.clone());
-                total_words += race.words(race.length);
-                total_time += race.minutes();
+// With --json, prints the race the session finished, or fails if it was abandoned.
+fn print_result(json: bool, session: &Session) -> io::Result<()> {
+    if !json {
+        return Ok(());
+    }
+    let (Some(race), Some(record)) = (&session.last_race, session.history.last()) else {
+        eprintln!("race abandoned, no result to print");
+        std::process::exit(1);
+    };
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&RaceResult::new(race, record))?
+    );
+    Ok(())
+}
 
-                last_race = Some(race);
+// Whether a run is the day's scored attempt. This is settled when the run starts, so restarting
+// or quitting a run that is going badly doesn't earn another go.
+#[derive(Clone, Debug, Default)]
+pub struct Attempt {
+    settled: bool,
+    // The challenge this run is the scored attempt at, if any.
+    scored: Option<Daily>,
+}
+
+impl Attempt {
+    // Takes the daily attempt once a run on the daily text has started. Returns whether the run
+    // is scored when this call settles it, None otherwise.
+    pub fn claim(
+        &mut self,
+        started: bool,
+        quote: &Quote,
+        username: &str,
+        source: &mut TextSource,
+    ) -> io::Result<Option<bool>> {
+        if self.settled || !started {
+            return Ok(None);
+        }
+        self.settled = true;
 
-                break 'race;
+        let daily = source.is_daily(quote);
+        if let Some(today) = source.daily.as_mut().filter(|_| daily) {
+            if daily::start(Path::new(DAILY_PATH), today, username, &quote.get_name())? {
+                self.scored = Some(today.clone());
             }
         }
+        Ok(Some(self.scored.is_some()))
     }
 
-    disable_raw_mode()?;
-    stdout().execute(LeaveAlternateScreen)?;
+    pub fn scored(&self) -> Option<&Daily> {
+        self.scored.as_ref()
+    }
+}
+
+// Keeps a finished race everywhere it goes: the stats file, the daily board when it was the
+// scored attempt, the --output file and the session.
+pub fn save_race(
+    race: Race,
+    quote: &Quote,
+    attempt: &Attempt,
+    username: &str,
+    options: &Options,
+    session: &mut Session,
+) -> io::Result<()> {
+    let record = Record::new(
+        &race,
+        username,
+        &quote.get_name(),
+        &options.keyboard.name,
+        session.key.as_deref(),
+    );
+    stats::append(Path::new(STATS_PATH), record.clone())?;
+    if let Some(today) = attempt.scored() {
+        daily::finish(Path::new(DAILY_PATH), today, &record)?;
+    }
+    if let Some(path) = &options.output {
+        stats::append_result(path, RaceResult::new(&race, &record))?;
+    }
+    session.history.push(record);
+    session.add(race);
     Ok(())
 }
 
-fn handle_race(c: char) -> io::Result<Keystroke> {
-    if event::poll(std::time::Duration::from_millis(4))? {
-        if let Event::Key(key) = event::read()? {
-            if key.kind == event::KeyEventKind::Press && key.code == KeyCode::Char(c) {
-                return Ok(Keystroke::Correct);
-            } else if key.code == KeyCode::Char('c')
-                && key.modifiers.contains(KeyModifiers::CONTROL)
-            {
-                return Ok(Keystroke::Quit);
-            } else if key.kind == event::KeyEventKind::Press {
-                return Ok(Keystroke::Wrong);
-            }
+pub struct Options {
+    pub keyboard: KeyboardLayout,
+    pub geometry: Geometry,
+    pub theme: Theme,
+    pub theme_name: String,
+    pub hud: Hud,
+    pub show_keyboard: bool,
+    // Pace caret speed in wpm, 0 for none.
+    pub pacer: u64,
+    pub correction: Correction,
+    pub split_interval: u128,
+    pub afk_timeout: u128,
+    pub source: TextSource,
+    // Where each finished race's result is added as JSON.
+    pub output: Option<PathBuf>,
+    // Leave after the first race instead of returning to the title screen.
+    pub once: bool,
+}
+
+// Runs a single unpausable race outside the screen stack, ending early once `limit` nanoseconds
+// have passed.
+async fn play(
+    terminal: &mut Terminal<impl Backend>,
+    events: &mut Events,
+    options: &Options,
+    quote: Quote,
+    limit: Option<u128>,
+) -> io::Result<Outcome> {
+    let mut race = RaceScreen::new(quote, options, limit).without_pause();
+    loop {
+        if let Some(outcome) = race.poll() {
+            return Ok(outcome);
+        }
+        terminal.draw(|frame| race.render(frame, options))?;
+        if let Some(outcome) = race.input(events.next_or_tick().await?) {
+            return Ok(outcome);
         }
     }
-    return Ok(Keystroke::Invalid);
 }
 
-fn draw_keyboard(frame: &mut Frame, area: &Rect, next_c: &str, layout: Option<&String>) {
-    let shift = false;
-    let layout = get_keyboard_layout(layout, shift);
-
-    let keyboard = Layout::new(
-        Direction::Vertical,
-        [
-            Constraint::Percentage(25),
-            Constraint::Percentage(25),
-            Constraint::Percentage(25),
-            Constraint::Percentage(25),
-        ],
-    )
-    .split(*area);
+async fn certify(
+    minutes: u64,
+    username: Option<String>,
+    layout: Option<String>,
+    format: Format,
+    penalty: Penalty,
+) -> io::Result<()> {
+    let config = Config::load();
+    let username = username
+        .or_else(|| config.username.clone())
+        .unwrap_or_else(|| String::from("default"));
+
+    // Only how the test looks comes from the config. Pacing and correction stay standard so
+    // certificates compare across people.
+    let options = Options {
+        keyboard: get_keyboard_layout(
+            layout.as_ref().or(config.layout.as_ref()),
+            Geometry::default(),
+            false,
+        ),
+        geometry: Geometry::default(),
+        theme: Theme::load(config.theme.as_ref()),
+        theme_name: config.theme.unwrap_or_else(|| String::from(THEMES[0])),
+        hud: config.hud,
+        show_keyboard: config.keyboard,
+        pacer: 0,
+        correction: Correction::default(),
+        split_interval: u128::from(config.split) * 1_000_000,
+        // A fixed-length test can't be paused, idle or not.
+        afk_timeout: 0,
+        source: TextSource::default(),
+        output: None,
+        once: false,
+    };
+    let name = format!("Certification test ({minutes} min)");
+    let text = certify::test_text(minutes);
 
-    let constraints = [Constraint::Percentage(10); 10];
-    let row_1 = Layout::new(Direction::Horizontal, constraints).split(keyboard[0]);
-    for (i, c) in layout.rows[0].iter().enumerate() {
-        let mut paragraph = Paragraph::new(c.clone())
-            .block(Block::bordered().border_type(BorderType::Rounded))
-            .alignment(Alignment::Center);
+    enable_raw_mode()?;
+    stdout().execute(EnterAlternateScreen)?;
+    stdout().execute(EnableBracketedPaste)?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(stdout()))?;
+    let mut events = Events::new();
+
+    let limit = minutes as u128 * 60 * 1e9 as u128;
+    let race = play(
+        &mut terminal,
+        &mut events,
+        &options,
+        Quote::new(name.clone(), text),
+        Some(limit),
+    )
+    .await;
 
-        if *c == next_c {
-            paragraph = paragraph.style(Style::default().fg(CORRECT));
-        }
+    disable_raw_mode()?;
+    stdout().execute(DisableBracketedPaste)?;
+    stdout().execute(LeaveAlternateScreen)?;
 
-        (*frame).render_widget(paragraph, row_1[i]);
+    // Certification tests allow no retries, so restarting forfeits the test too.
+    let Outcome::Finished(race) = race? else {
+        println!("Test abandoned, no certificate issued.");
+        return Ok(());
+    };
+
+    let key = verify::install_key().ok();
+    let record = Record::new(
+        &race,
+        &username,
+        &name,
+        &options.keyboard.name,
+        key.as_deref(),
+    );
+    let flags = record.flags.clone();
+    let certificate = Certificate::new(&race, &record, minutes, penalty);
+    stats::append(Path::new(STATS_PATH), record)?;
+
+    // A certificate vouches for the typist, so none is issued for pasted or scripted input.
+    if !flags.is_empty() {
+        println!(
+            "Test flagged for {}, no certificate issued.",
+            anticheat::describe(&flags)
+        );
+        return Ok(());
     }
 
-    let row_2 = Layout::new(Direction::Horizontal, constraints).split(keyboard[1]);
+    let path = format!(
+        "certificate-{username}-{}.{}",
+        certificate.timestamp,
+        format.extension()
+    );
+    let rendered = certificate.render(format);
+    fs::write(&path, &rendered)?;
 
-    for (i, c) in layout.rows[1].iter().enumerate() {
-        let mut paragraph = Paragraph::new(c.clone())
-            .block(Block::bordered().border_type(BorderType::Rounded))
-            .alignment(Alignment::Center);
+    if format == Format::Text {
+        print!("{rendered}");
+    }
+    println!("Certificate written to {path}");
+    Ok(())
+}
 
-        if *c == next_c {
-            paragraph = paragraph.style(Style::default().fg(CORRECT));
+fn verify_records(path: &Path) -> io::Result<()> {
+    let records = stats::load(path)?;
+    let key = verify::install_key()?;
+    let mut failed = 0;
+
+    for (index, record) in records.iter().enumerate() {
+        let problems = verify::problems(record, &key);
+        let name = format!(
+            "#{} {} {:.0} wpm ({})",
+            index + 1,
+            record.user,
+            record.wpm,
+            record.quote
+        );
+        if problems.is_empty() && !record.flags.is_empty() {
+            println!(
+                "ok      {name}, flagged: {}",
+                anticheat::describe(&record.flags)
+            );
+        } else if problems.is_empty() {
+            println!("ok      {name}");
+        } else {
+            failed += 1;
+            println!("FAILED  {name}: {}", problems.join("; "));
         }
+    }
 
-        (*frame).render_widget(paragraph, row_2[i]);
+    println!(
+        "{} of {} records verified",
+        records.len() - failed,
+        records.len()
+    );
+    if failed > 0 {
+        std::process::exit(1);
     }
+    Ok(())
+}
 
-    let row_3 = Layout::new(Direction::Horizontal, constraints).split(keyboard[2]);
+async fn analyze_layout(corpus: &str, theme: &Theme) -> io::Result<()> {
+    let stats = analyze::analyze_all(corpus);
+    let mut selected: usize = 0;
 
-    for (i, c) in layout.rows[2].iter().enumerate() {
-        let mut paragraph = Paragraph::new(c.clone())
-            .block(Block::bordered().border_type(BorderType::Rounded))
-            .alignment(Alignment::Center);
+    enable_raw_mode()?;
+    stdout().execute(EnterAlternateScreen)?;
+    stdout().execute(EnableBracketedPaste)?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(stdout()))?;
+    let mut events = Events::new();
 
-        if *c == next_c {
-            paragraph = paragraph.style(Style::default().fg(CORRECT));
+    loop {
+        terminal.draw(|frame| ui::analysis(frame, &stats, selected, theme))?;
+        match handle_events(&mut events).await? {
+            Some(KeyCode::Left) => selected = (selected + stats.len() - 1) % stats.len(),
+            Some(KeyCode::Right) => selected = (selected + 1) % stats.len(),
+            Some(KeyCode::Char('q') | KeyCode::Esc) => break,
+            _ => (),
         }
-
-        (*frame).render_widget(paragraph, row_3[i]);
     }
 
-    let row_4 = Layout::new(
-        Direction::Horizontal,
-        [
-            Constraint::Percentage(30),
-            Constraint::Percentage(40),
-            Constraint::Percentage(30),
-        ],
-    )
-    .split(keyboard[3]);
+    disable_raw_mode()?;
+    stdout().execute(DisableBracketedPaste)?;
+    stdout().execute(LeaveAlternateScreen)?;
+    Ok(())
+}
 
-    let mut spacebar = Paragraph::new("qwerty")
-        .block(Block::bordered().border_type(BorderType::Rounded))
-        .alignment(Alignment::Center);
+// Waits for the next keystroke of the race, returning it with the moment it arrived.
+async fn handle_race(events: &mut Events, c: char) -> io::Result<(Keystroke, Instant)> {
+    Ok(keystroke(events.next_or_tick().await?, c))
+}
 
-    if next_c == " " {
-        spacebar = spacebar.style(Style::default().fg(CORRECT));
-    }
+// Reads an event as a race keystroke against the expected character `c`.
+fn keystroke(event: AppEvent, c: char) -> (Keystroke, Instant) {
+    let (key, at) = match event {
+        AppEvent::Key(key, at) => (key, at),
+        AppEvent::Paste(at) => return (Keystroke::Paste, at),
+        AppEvent::Resize | AppEvent::Tick => return (Keystroke::Invalid, Instant::now()),
+    };
+
+    let control = key.modifiers.contains(KeyModifiers::CONTROL);
+    let keystroke = if key.code == KeyCode::Char('c') && control {
+        Keystroke::Quit
+    } else if key.kind != event::KeyEventKind::Press {
+        Keystroke::Invalid
+    } else if key.code == KeyCode::Char('n') && control {
+        Keystroke::Skip
+    } else if key.code == KeyCode::Char(c) {
+        Keystroke::Correct
+    } else if key.code == KeyCode::Esc {
+        Keystroke::Pause
+    } else if key.code == KeyCode::Tab {
+        Keystroke::Tab
+    } else if key.code == KeyCode::Enter {
+        Keystroke::Enter
+    } else {
+        let typed = match key.code {
+            KeyCode::Char(typed) => Some(typed),
+            _ => None,
+        };
+        Keystroke::Wrong(typed)
+    };
+    (keystroke, at)
+}
 
-    (*frame).render_widget(spacebar, row_4[1]);
+fn draw_keyboard(
+    frame: &mut Frame,
+    area: &Rect,
+    next_c: &str,
+    layout: &KeyboardLayout,
+    theme: &Theme,
+) {
+    draw_keys(frame, area, layout, |c| {
+        if c == next_c {
+            theme.highlight()
+        } else {
+            Style::default()
+        }
+    });
 }
 
-fn handle_events() -> io::Result<KeyCode> {
-    loop {
-        if event::poll(std::time::Duration::from_millis(4))? {
-            if let Event::Key(key) = event::read()? {
-                if key.kind == event::KeyEventKind::Press {
-                    return Ok(key.code);
-                }
-            }
+fn draw_heatmap(
+    frame: &mut Frame,
+    area: &Rect,
+    layout: &KeyboardLayout,
+    keys: &HashMap<String, u64>,
+    theme: &Theme,
+) {
+    let max = keys.values().copied().max().unwrap_or(0).max(1) as f64;
+
+    draw_keys(frame, area, layout, |c| {
+        let heat = keys.get(c).copied().unwrap_or(0) as f64 / max;
+        theme.heat(heat)
+    });
+}
+
+fn draw_keys(
+    frame: &mut Frame,
+    area: &Rect,
+    layout: &KeyboardLayout,
+    style: impl Fn(&str) -> Style,
+) {
+    let units = layout.units().max(1) as u32;
+    let scale = |x: u16| (x as u32 * area.width as u32 / units) as u16;
+
+    for key in layout.keys.iter() {
+        let (left, right) = (scale(key.x), scale(key.x + key.width));
+        if key.y + KEY_HEIGHT > area.height {
+            continue;
         }
+
+        let paragraph = Paragraph::new(key.legend.clone())
+            .block(Block::bordered().border_type(BorderType::Rounded))
+            .alignment(Alignment::Center)
+            .style(style(&key.label));
+
+        let rect = Rect::new(area.x + left, area.y + key.y, right - left, KEY_HEIGHT);
+        (*frame).render_widget(paragraph, rect);
     }
 }
 
-#[allow(dead_code)]
-fn is_shifted() -> io::Result<bool> {
+async fn handle_events(events: &mut Events) -> io::Result<Option<KeyCode>> {
     loop {
-        if event::poll(std::time::Duration::from_millis(4))? {
-            if let Event::Key(key) = event::read()? {
-                if key.kind == event::KeyEventKind::Press
-                    && key.modifiers.contains(KeyModifiers::SHIFT)
-                {
-                    return Ok(true);
+        match events.next().await? {
+            AppEvent::Resize => return Ok(None),
+            event => {
+                if let Some(code) = pressed(&event) {
+                    return Ok(Some(code));
                 }
             }
-            return Ok(false);
         }
     }
 }
+
+fn pressed(event: &AppEvent) -> Option<KeyCode> {
+    match event {
+        AppEvent::Key(key, _) if key.kind == event::KeyEventKind::Press => Some(key.code),
+        _ => None,
+    }
+}
@@ -0,0 +1,202 @@
+use crate::anticheat;
+use crate::engine::RaceEngine;
+use crate::events::Events;
+use crate::stats::{self, Metric};
+use crate::types::{Keystroke, Quote, Session};
+
+use super::{handle_events, handle_race, save_race, Attempt, Options};
+
+use crossterm::event::KeyCode;
+use std::io::{self, stderr, stdout, Write};
+use std::time::Instant;
+
+fn speak(out: &mut dyn Write, line: &str) -> io::Result<()> {
+    write!(out, "{line}\r\n")?;
+    out.flush()
+}
+
+fn describe(c: char) -> String {
+    match c {
+        ' ' => String::from("space"),
+        _ => c.to_string(),
+    }
+}
+
+pub async fn run(
+    username: &str,
+    mut session: Session,
+    options: &Options,
+    events: &mut Events,
+) -> io::Result<Session> {
+    let mut source = options.source.clone();
+    // A one-shot race keeps stdout for its JSON result, so everything spoken goes to stderr.
+    let say = |line: &str| {
+        if options.once {
+            speak(&mut stderr(), line)
+        } else {
+            speak(&mut stdout(), line)
+        }
+    };
+
+    say(&format!("Typing as {username}."))?;
+
+    let mut next: Option<Quote> = None;
+
+    'game: loop {
+        if next.is_none() && !options.once {
+            say("Press s to start, q to quit.")?;
+            loop {
+                match handle_events(events).await? {
+                    Some(KeyCode::Char('s')) => break,
+                    Some(KeyCode::Char('q')) => break 'game,
+                    _ => (),
+                }
+            }
+        }
+
+        let quote = match next.take() {
+            Some(quote) => quote,
+            None => source.next_text(),
+        };
+        let words = quote.get_text().split_whitespace().count();
+
+        say(&format!("Quote: {}", quote.get_name()))?;
+        say(&quote.get_text())?;
+        say("Start typing. Escape pauses, Tab then Enter restarts, Ctrl+N skips the quote and Ctrl+C abandons the race.")?;
+
+        let mut typed_words = 0;
+
+        let mut engine = RaceEngine::new(&quote.get_text(), Instant::now())
+            .with_split_interval(options.split_interval)
+            .with_afk_timeout(options.afk_timeout)
+            .with_correction(options.correction)
+            .with_limit(source.limit());
+        let mut restarting = false;
+        let mut attempt = Attempt::default();
+
+        loop {
+            let now = Instant::now();
+            if engine.tick(now) {
+                say("Paused while idle. Press any key to resume.")?;
+            }
+
+            let Some(c) = engine.expected().filter(|_| !engine.is_finished(now)) else {
+                let race = engine.finish(now);
+                if !race.is_scorable(options.split_interval) {
+                    say("Too quick to score, starting again.")?;
+                    next = Some(Quote::new(quote.get_name(), quote.get_text()));
+                    break;
+                }
+
+                say(&format!(
+                    "Finished: {:.0} wpm, {:.2}% accuracy, {:.1} seconds.",
+                    race.wpm(),
+                    race.accuracy(),
+                    race.time()
+                ))?;
+                say(&format!(
+                    "Consistency {:.0}%, burst {:.0} wpm, reaction {:.2} seconds.",
+                    race.consistency(),
+                    race.burst(),
+                    race.reaction_time()
+                ))?;
+
+                let flags = race.flags();
+                if !flags.is_empty() {
+                    say(&format!(
+                        "Flagged for {}, not counted towards personal bests.",
+                        anticheat::describe(&flags)
+                    ))?;
+                }
+
+                let mistakes = race.mistakes();
+                if !mistakes.is_empty() {
+                    let words: Vec<String> = mistakes.into_iter().map(|(word, _)| word).collect();
+                    say(&format!("Mistakes: {}", words.join(", ")))?;
+                }
+
+                let best = stats::personal_best(&session.history, username, Metric::Wpm)
+                    .map(|best| best.wpm);
+                save_race(race, &quote, &attempt, username, options, &mut session)?;
+                if let Some(today) = attempt.scored() {
+                    say(&format!("Scored for the {}.", today.name()))?;
+                }
+                if let Some(best) = best {
+                    say(&format!("Personal best: {best:.0} wpm"))?;
+                }
+                if let Some(average) = session.average() {
+                    say(&format!("Session average: {:.0} wpm", average))?;
+                }
+                if options.once {
+                    break 'game;
+                }
+                break;
+            };
+
+            let (keystroke, at) = handle_race(events, c).await?;
+            if matches!(keystroke, Keystroke::Invalid) {
+                continue;
+            }
+            engine.active(at);
+
+            if engine.is_paused() && !matches!(keystroke, Keystroke::Quit | Keystroke::Skip) {
+                engine.resume(at);
+                say("Resumed.")?;
+                continue;
+            }
+
+            let tabbed = std::mem::take(&mut restarting);
+            match keystroke {
+                Keystroke::Correct => {
+                    engine.input(Some(c), at);
+                    if c == ' ' || engine.expected().is_none() {
+                        typed_words += 1;
+                        say(&format!("{typed_words} of {words} words"))?;
+                    }
+                }
+                Keystroke::Enter if tabbed => {
+                    say("Restarting.")?;
+                    next = Some(Quote::new(quote.get_name(), quote.get_text()));
+                    break;
+                }
+                Keystroke::Wrong(_) | Keystroke::Enter => {
+                    let typed = match keystroke {
+                        Keystroke::Wrong(typed) => typed,
+                        _ => None,
+                    };
+                    engine.input(typed, at);
+                    say(&format!("Error, expected {}", describe(c)))?;
+                }
+                Keystroke::Tab => restarting = true,
+                Keystroke::Pause if engine.is_started() => {
+                    engine.pause(at);
+                    say("Paused. Press any key to resume.")?;
+                }
+                Keystroke::Skip => {
+                    say("Skipping to a new quote.")?;
+                    next = Some(source.next_text());
+                    break;
+                }
+                Keystroke::Paste => {
+                    engine.paste(at);
+                    say("Pasting is not allowed.")?;
+                }
+                Keystroke::Quit => {
+                    say("Race abandoned.")?;
+                    if options.once {
+                        break 'game;
+                    }
+                    break;
+                }
+                Keystroke::Pause | Keystroke::Invalid => (),
+            }
+
+            let settled = attempt.claim(engine.is_started(), &quote, username, &mut source)?;
+            if settled == Some(false) && source.is_daily(&quote) {
+                say("Practice only, today's attempt is already used.")?;
+            }
+        }
+    }
+
+    Ok(session)
+}
@@ -0,0 +1,184 @@
+use crate::catalog;
+use crate::cli::ExportFormat;
+use crate::daily::{self, Daily};
+use crate::stats::{self, Metric, Record};
+use crate::types::LAYOUTS;
+use crate::utils::date;
+
+use std::fs;
+use std::io::{self, stdout, Write};
+use std::path::Path;
+
+const METRICS: [(&str, Metric); 5] = [
+    ("wpm", Metric::Wpm),
+    ("accuracy", Metric::Accuracy),
+    ("consistency", Metric::Consistency),
+    ("burst", Metric::Burst),
+    ("reaction", Metric::Reaction),
+];
+
+pub fn stats(path: &Path, user: Option<&str>) -> io::Result<()> {
+    let records = stats::load(path)?;
+
+    let Some(user) = user else {
+        for (rank, record) in stats::leaderboard(&records, Metric::Wpm)
+            .into_iter()
+            .enumerate()
+        {
+            println!(
+                "{:>3}. {:<16} {:>4.0} wpm {:>7.2}%  {}",
+                rank + 1,
+                record.user,
+                record.wpm,
+                record.accuracy,
+                record.quote
+            );
+        }
+        return Ok(());
+    };
+
+    let own: Vec<&Record> = records
+        .iter()
+        .filter(|record| record.user == user && record.flags.is_empty())
+        .collect();
+    if own.is_empty() {
+        println!("No races recorded for {user}.");
+        return Ok(());
+    }
+
+    let average = own.iter().map(|record| record.wpm).sum::<f64>() / own.len() as f64;
+    println!("{user}: {} races, {average:.0} wpm average", own.len());
+    for (name, metric) in METRICS {
+        if let Some(best) = stats::personal_best(&records, user, metric) {
+            println!(
+                "best {name:<12} {:>8}  {}",
+                metric.display(best),
+                best.quote
+            );
+        }
+    }
+    Ok(())
+}
+
+pub fn daily(path: &Path, team: Option<String>, date: Option<String>) -> io::Result<()> {
+    let entries = daily::load(path)?;
+    let mut today = Daily::today(team);
+    if let Some(date) = date {
+        today.date = date;
+    }
+
+    let board = daily::leaderboard(&entries, &today);
+    if board.is_empty() {
+        println!("No scored attempts for the {} yet.", today.name());
+        return Ok(());
+    }
+
+    println!("{}: {}", today.name(), board[0].quote);
+    for (rank, entry) in board.into_iter().enumerate() {
+        println!(
+            "{:>3}. {:<16} {:>4.0} wpm {:>7.2}%",
+            rank + 1,
+            entry.user,
+            entry.wpm,
+            entry.accuracy
+        );
+    }
+    Ok(())
+}
+
+pub fn history(path: &Path, user: Option<&str>, limit: usize) -> io::Result<()> {
+    let records = stats::load(path)?;
+
+    for record in records
+        .iter()
+        .rev()
+        .filter(|record| user.is_none_or(|user| record.user == user))
+        .take(limit)
+    {
+        println!(
+            "{}  {:<16} {:>4.0} wpm {:>7.2}%  {}{}",
+            date(record.timestamp),
+            record.user,
+            record.wpm,
+            record.accuracy,
+            record.quote,
+            if record.flags.is_empty() {
+                ""
+            } else {
+                " (flagged)"
+            }
+        );
+    }
+    Ok(())
+}
+
+pub fn export(path: &Path, format: ExportFormat, output: Option<&Path>) -> io::Result<()> {
+    let records = stats::load(path)?;
+    let contents = match format {
+        ExportFormat::Json => serde_json::to_string_pretty(&records)? + "\n",
+        ExportFormat::Csv => csv(&records),
+    };
+
+    match output {
+        Some(output) => fs::write(output, contents),
+        None => stdout().write_all(contents.as_bytes()),
+    }
+}
+
+fn csv(records: &[Record]) -> String {
+    let mut csv = String::from(
+        "user,quote,layout,timestamp,wpm,raw,accuracy,consistency,burst,slowest_word,reaction,time,flags\n",
+    );
+    for record in records {
+        let flags: Vec<String> = record
+            .flags
+            .iter()
+            .map(|flag| format!("{flag:?}"))
+            .collect();
+        let fields = [
+            field(&record.user),
+            field(&record.quote),
+            field(&record.layout),
+            record.timestamp.to_string(),
+            format!("{:.2}", record.wpm),
+            format!("{:.2}", record.raw),
+            format!("{:.2}", record.accuracy),
+            format!("{:.2}", record.consistency),
+            format!("{:.2}", record.burst),
+            field(record.slowest_word.as_deref().unwrap_or_default()),
+            format!("{:.3}", record.reaction),
+            format!("{:.3}", record.time),
+            field(&flags.join(" ")),
+        ];
+        csv.push_str(&fields.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+// Quotes a CSV field when it would otherwise break the row.
+fn field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        String::from(value)
+    }
+}
+
+pub fn layouts() {
+    for layout in LAYOUTS {
+        println!("{layout}");
+    }
+}
+
+pub fn quotes() {
+    for entry in catalog::entries(&[], "") {
+        println!(
+            "{:<40} {:>3} words  {:<6}  {}",
+            entry.name,
+            entry.words(),
+            entry.length().name(),
+            entry.tags.join(", ")
+        );
+    }
+}
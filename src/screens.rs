@@ -0,0 +1,744 @@
+use crate::catalog::{self, Entry, Filter, Length, Pick};
+use crate::config::{word_lists, Config};
+use crate::engine::{Correction, RaceEngine};
+use crate::events::{AppEvent, Events};
+use crate::source::{load_text, Mode};
+use crate::stats::Record;
+use crate::theme::{Theme, THEMES};
+use crate::types::{Keystroke, Quote, Race, ResultsTab, ResultsView, Session, Status, LAYOUTS};
+use crate::ui;
+use crate::utils::get_keyboard_layout;
+
+use super::{keystroke, pressed, save_race, Attempt, Options};
+
+use crossterm::event::{KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::prelude::*;
+use std::io;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+// State shared by every screen.
+pub struct App {
+    pub username: String,
+    pub session: Session,
+    pub options: Options,
+    // The config file as saved, which settings changes are written back to.
+    pub config: Config,
+}
+
+pub enum Transition {
+    Stay,
+    Push(Box<dyn Screen>),
+    Replace(Box<dyn Screen>),
+    Back,
+    Quit,
+}
+
+pub trait Screen {
+    fn draw(&self, frame: &mut Frame, app: &App);
+
+    fn handle(&mut self, event: AppEvent, app: &mut App) -> io::Result<Transition>;
+
+    // Runs before every frame, for screens that move on without input.
+    fn update(&mut self, _app: &mut App) -> io::Result<Transition> {
+        Ok(Transition::Stay)
+    }
+
+    // Timed screens also wake up on ticks, not just on input.
+    fn is_timed(&self) -> bool {
+        false
+    }
+}
+
+// Runs screens from a stack until the last one is popped or one quits.
+pub async fn run(
+    terminal: &mut Terminal<impl Backend>,
+    events: &mut Events,
+    app: &mut App,
+    first: Box<dyn Screen>,
+) -> io::Result<()> {
+    let mut stack = vec![first];
+
+    while let Some(screen) = stack.last_mut() {
+        let mut transition = screen.update(app)?;
+        if matches!(transition, Transition::Stay) {
+            terminal.draw(|frame| screen.draw(frame, app))?;
+            let event = if screen.is_timed() {
+                events.next_or_tick().await?
+            } else {
+                events.next().await?
+            };
+            transition = screen.handle(event, app)?;
+        }
+
+        match transition {
+            Transition::Stay => (),
+            Transition::Push(next) => stack.push(next),
+            Transition::Replace(next) => {
+                stack.pop();
+                stack.push(next);
+            }
+            Transition::Back => {
+                stack.pop();
+            }
+            Transition::Quit => break,
+        }
+    }
+
+    Ok(())
+}
+
+pub struct Title;
+
+impl Screen for Title {
+    fn draw(&self, frame: &mut Frame, app: &App) {
+        let menu = if app.session.last_race.is_some() {
+            "(s) start | (l) lobby | (b) browse | (r) results | (h) history | (o) settings | (q) quit"
+        } else {
+            "(s) start | (l) lobby | (b) browse | (h) history | (o) settings | (q) quit"
+        };
+        let menu = match &app.options.source.daily {
+            Some(today) if today.attempted => format!("{}: practice | {menu}", today.name()),
+            Some(today) => format!("{}: scored attempt | {menu}", today.name()),
+            None => String::from(menu),
+        };
+        ui::title(
+            frame,
+            &app.session,
+            &app.username,
+            &menu,
+            &app.options.theme,
+        );
+    }
+
+    fn handle(&mut self, event: AppEvent, app: &mut App) -> io::Result<Transition> {
+        Ok(match pressed(&event) {
+            Some(KeyCode::Char('s')) => {
+                Transition::Push(Box::new(RaceScreen::next(&mut app.options)))
+            }
+            Some(KeyCode::Char('l')) => Transition::Push(Box::new(Lobby::new(&mut app.options))),
+            Some(KeyCode::Char('b')) => Transition::Push(Box::new(Browser::new(app))),
+            Some(KeyCode::Char('r')) if app.session.last_race.is_some() => {
+                Transition::Push(Box::new(Results::default()))
+            }
+            Some(KeyCode::Char('h')) => Transition::Push(Box::new(History::default())),
+            Some(KeyCode::Char('o')) => Transition::Push(Box::new(Settings::default())),
+            Some(KeyCode::Char('q')) => Transition::Quit,
+            _ => Transition::Stay,
+        })
+    }
+}
+
+pub enum Outcome {
+    Finished(Race),
+    Abandoned,
+    Restart,
+    Skip,
+}
+
+pub struct RaceScreen {
+    quote: Quote,
+    engine: RaceEngine,
+    restarting: bool,
+    attempt: Attempt,
+    pausable: bool,
+}
+
+impl RaceScreen {
+    // Races `quote`, ending early once `limit` nanoseconds have passed.
+    pub fn new(quote: Quote, options: &Options, limit: Option<u128>) -> RaceScreen {
+        let engine = RaceEngine::new(&quote.get_text(), Instant::now())
+            .with_split_interval(options.split_interval)
+            .with_afk_timeout(options.afk_timeout)
+            .with_limit(limit)
+            .with_correction(options.correction);
+        RaceScreen {
+            quote,
+            engine,
+            restarting: false,
+            attempt: Attempt::default(),
+            pausable: true,
+        }
+    }
+
+    // For tests that must run their full length: Esc no longer pauses.
+    pub fn without_pause(mut self) -> RaceScreen {
+        self.pausable = false;
+        self
+    }
+
+    // A race over the next text from the configured source.
+    pub fn next(options: &mut Options) -> RaceScreen {
+        let quote = options.source.next_text();
+        RaceScreen::new(quote, options, options.source.limit())
+    }
+
+    pub fn poll(&mut self) -> Option<Outcome> {
+        let now = Instant::now();
+        self.engine.tick(now);
+        self.engine
+            .is_finished(now)
+            .then(|| Outcome::Finished(self.engine.clone().finish(now)))
+    }
+
+    pub fn input(&mut self, event: AppEvent) -> Option<Outcome> {
+        let c = self.engine.expected().unwrap_or_default();
+        let (keystroke, at) = keystroke(event, c);
+        if matches!(keystroke, Keystroke::Invalid) {
+            return None;
+        }
+        self.engine.active(at);
+
+        if self.engine.is_paused() {
+            match keystroke {
+                Keystroke::Quit => return Some(Outcome::Abandoned),
+                Keystroke::Skip => return Some(Outcome::Skip),
+                _ => self.engine.resume(at),
+            }
+            return None;
+        }
+
+        let tabbed = std::mem::take(&mut self.restarting);
+        match keystroke {
+            Keystroke::Correct => {
+                self.engine.input(Some(c), at);
+            }
+            Keystroke::Enter if tabbed => return Some(Outcome::Restart),
+            Keystroke::Wrong(typed) => {
+                self.engine.input(typed, at);
+            }
+            Keystroke::Enter => {
+                self.engine.input(None, at);
+            }
+            Keystroke::Tab => self.restarting = true,
+            Keystroke::Pause if self.pausable => self.engine.pause(at),
+            Keystroke::Paste => self.engine.paste(at),
+            Keystroke::Skip => return Some(Outcome::Skip),
+            Keystroke::Quit => return Some(Outcome::Abandoned),
+            Keystroke::Pause | Keystroke::Invalid => (),
+        }
+        None
+    }
+
+    pub fn render(&self, frame: &mut Frame, options: &Options) {
+        let now = Instant::now();
+        let mut status = self.engine.status(now);
+        if options.pacer > 0 && self.engine.is_started() {
+            status.pacer = Some(self.engine.pace(now, options.pacer));
+        }
+        let name = match &options.source.daily {
+            Some(today) => format!("{} | {}", today.name(), self.quote.get_name()),
+            None => self.quote.get_name(),
+        };
+        ui::race(frame, frame.size(), &name, &self.engine, &status, options);
+    }
+
+    fn conclude(&self, outcome: Outcome, app: &mut App) -> io::Result<Transition> {
+        let next = match outcome {
+            // Too quick to score, so it is run again instead of recorded.
+            Outcome::Finished(race) if !race.is_scorable(app.options.split_interval) => {
+                self.quote.clone()
+            }
+            Outcome::Finished(race) => {
+                save_race(
+                    race,
+                    &self.quote,
+                    &self.attempt,
+                    &app.username,
+                    &app.options,
+                    &mut app.session,
+                )?;
+                if app.options.once {
+                    return Ok(Transition::Quit);
+                }
+                return Ok(Transition::Replace(Box::new(Results::default())));
+            }
+            Outcome::Abandoned => return Ok(Transition::Back),
+            Outcome::Restart => self.quote.clone(),
+            Outcome::Skip => app.options.source.next_text(),
+        };
+        Ok(Transition::Replace(Box::new(RaceScreen::new(
+            next,
+            &app.options,
+            app.options.source.limit(),
+        ))))
+    }
+}
+
+impl Screen for RaceScreen {
+    fn draw(&self, frame: &mut Frame, app: &App) {
+        self.render(frame, &app.options);
+    }
+
+    fn handle(&mut self, event: AppEvent, app: &mut App) -> io::Result<Transition> {
+        let outcome = self.input(event);
+        self.attempt.claim(
+            self.engine.is_started(),
+            &self.quote,
+            &app.username,
+            &mut app.options.source,
+        )?;
+        match outcome {
+            Some(outcome) => self.conclude(outcome, app),
+            None => Ok(Transition::Stay),
+        }
+    }
+
+    fn update(&mut self, app: &mut App) -> io::Result<Transition> {
+        match self.poll() {
+            Some(outcome) => self.conclude(outcome, app),
+            None => Ok(Transition::Stay),
+        }
+    }
+
+    fn is_timed(&self) -> bool {
+        true
+    }
+}
+
+#[derive(Default)]
+pub struct Results {
+    view: ResultsView,
+}
+
+impl Screen for Results {
+    fn draw(&self, frame: &mut Frame, app: &App) {
+        let menu = if self.view.tab == ResultsTab::Chart {
+            "(s) start | (esc) back | (q) quit | (←/→) tabs | (↑/↓) intervals | (c) mode"
+        } else {
+            "(s) start | (esc) back | (q) quit | (←/→) tabs"
+        };
+        ui::title(frame, &app.session, &app.username, menu, &app.options.theme);
+
+        if let Some(race) = &app.session.last_race {
+            ui::results(
+                frame,
+                race,
+                self.view,
+                &app.options.keyboard,
+                &ui::Best::new(&app.session.history, &app.username),
+                &app.options.theme,
+            );
+        }
+    }
+
+    fn handle(&mut self, event: AppEvent, app: &mut App) -> io::Result<Transition> {
+        let chart = self.view.tab == ResultsTab::Chart;
+        match pressed(&event) {
+            Some(KeyCode::Char('s')) => {
+                return Ok(Transition::Replace(Box::new(RaceScreen::next(
+                    &mut app.options,
+                ))))
+            }
+            Some(KeyCode::Esc | KeyCode::Char('r')) => return Ok(Transition::Back),
+            Some(KeyCode::Char('q')) => return Ok(Transition::Quit),
+            Some(KeyCode::Right) => self.view.tab = self.view.tab.next(),
+            Some(KeyCode::Left) => self.view.tab = self.view.tab.previous(),
+            Some(KeyCode::Char('c')) if chart => self.view.mode = self.view.mode.next(),
+            Some(KeyCode::Up) if chart => self.view.interval = self.view.interval.saturating_sub(1),
+            Some(KeyCode::Down) if chart => {
+                let count = app
+                    .session
+                    .last_race
+                    .as_ref()
+                    .map_or(0, Race::interval_count);
+                self.view.interval = (self.view.interval + 1).min(count.saturating_sub(1));
+            }
+            _ => (),
+        }
+        Ok(Transition::Stay)
+    }
+}
+
+// Past races by this user, newest first.
+fn own_records(app: &App) -> Vec<&Record> {
+    app.session
+        .history
+        .iter()
+        .rev()
+        .filter(|record| record.user == app.username)
+        .collect()
+}
+
+#[derive(Default)]
+pub struct History {
+    selected: usize,
+}
+
+impl Screen for History {
+    fn draw(&self, frame: &mut Frame, app: &App) {
+        ui::history(frame, &own_records(app), self.selected, &app.options.theme);
+    }
+
+    fn handle(&mut self, event: AppEvent, app: &mut App) -> io::Result<Transition> {
+        match pressed(&event) {
+            Some(KeyCode::Esc | KeyCode::Char('q')) => return Ok(Transition::Back),
+            Some(KeyCode::Up) => self.selected = self.selected.saturating_sub(1),
+            Some(KeyCode::Down) => {
+                let count = own_records(app).len();
+                self.selected = (self.selected + 1).min(count.saturating_sub(1));
+            }
+            _ => (),
+        }
+        Ok(Transition::Stay)
+    }
+}
+
+// Previews the next quote before racing it.
+pub struct Lobby {
+    quote: Quote,
+}
+
+impl Lobby {
+    pub fn new(options: &mut Options) -> Lobby {
+        Lobby {
+            quote: options.source.next_text(),
+        }
+    }
+}
+
+impl Screen for Lobby {
+    fn draw(&self, frame: &mut Frame, app: &App) {
+        let best = own_records(app)
+            .into_iter()
+            .filter(|record| record.quote == self.quote.get_name() && record.flags.is_empty())
+            .map(|record| record.wpm)
+            .reduce(f64::max);
+        ui::lobby(frame, &self.quote, best, &app.options.theme);
+    }
+
+    fn handle(&mut self, event: AppEvent, app: &mut App) -> io::Result<Transition> {
+        Ok(match pressed(&event) {
+            Some(KeyCode::Enter) => Transition::Replace(Box::new(RaceScreen::new(
+                self.quote.clone(),
+                &app.options,
+                app.options.source.limit(),
+            ))),
+            Some(KeyCode::Char('n')) => {
+                self.quote = app.options.source.next_text();
+                Transition::Stay
+            }
+            Some(KeyCode::Esc | KeyCode::Char('q')) => Transition::Back,
+            _ => Transition::Stay,
+        })
+    }
+}
+
+// Searches, filters and picks from the built-in quotes.
+pub struct Browser {
+    entries: Vec<Entry>,
+    filter: Filter,
+    selected: usize,
+}
+
+impl Browser {
+    pub fn new(app: &App) -> Browser {
+        Browser {
+            entries: catalog::entries(&app.session.history, &app.username),
+            filter: Filter::default(),
+            selected: 0,
+        }
+    }
+}
+
+// The next of `values` after `current`, where None means no filter and comes before the first.
+fn next_filter<T: Copy + PartialEq>(values: &[T], current: Option<T>) -> Option<T> {
+    match current {
+        None => values.first().copied(),
+        Some(current) => values
+            .iter()
+            .position(|value| *value == current)
+            .and_then(|index| values.get(index + 1))
+            .copied(),
+    }
+}
+
+impl Screen for Browser {
+    fn draw(&self, frame: &mut Frame, app: &App) {
+        let matches = self.filter.apply(&self.entries);
+        ui::browser(
+            frame,
+            &matches,
+            &self.filter,
+            self.selected,
+            &app.options.theme,
+        );
+    }
+
+    fn handle(&mut self, event: AppEvent, app: &mut App) -> io::Result<Transition> {
+        let AppEvent::Key(key, _) = event else {
+            return Ok(Transition::Stay);
+        };
+        if key.kind != KeyEventKind::Press {
+            return Ok(Transition::Stay);
+        }
+
+        let matches = self.filter.apply(&self.entries);
+        let control = key.modifiers.contains(KeyModifiers::CONTROL);
+        let chosen = match key.code {
+            KeyCode::Esc => return Ok(Transition::Back),
+            KeyCode::Enter => matches.get(self.selected).copied(),
+            KeyCode::Char('r') if control => catalog::pick(&matches, Pick::Random),
+            KeyCode::Char('n') if control => catalog::pick(&matches, Pick::Unplayed),
+            KeyCode::Char('w') if control => catalog::pick(&matches, Pick::Worst),
+            _ => None,
+        };
+        if let Some(entry) = chosen {
+            return Ok(Transition::Replace(Box::new(RaceScreen::new(
+                entry.quote(),
+                &app.options,
+                None,
+            ))));
+        }
+
+        match key.code {
+            KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down => self.selected += 1,
+            KeyCode::Tab => self.filter.length = next_filter(&Length::ALL, self.filter.length),
+            KeyCode::Char('t') if control => {
+                self.filter.tag = next_filter(&catalog::tags(), self.filter.tag)
+            }
+            KeyCode::Char('l') if control => {
+                self.filter.language = next_filter(&catalog::languages(), self.filter.language)
+            }
+            KeyCode::Char(c) if !control => self.filter.query.push(c),
+            KeyCode::Backspace => {
+                self.filter.query.pop();
+            }
+            _ => (),
+        }
+        let count = self.filter.apply(&self.entries).len();
+        self.selected = self.selected.min(count.saturating_sub(1));
+        Ok(Transition::Stay)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Setting {
+    Layout,
+    Theme,
+    Mode,
+    Words,
+    Time,
+    WordList,
+    Correction,
+    Pacer,
+    Keyboard,
+    HudWpm,
+    HudAccuracy,
+    HudTime,
+    HudProgress,
+}
+
+const SETTINGS: [Setting; 13] = [
+    Setting::Layout,
+    Setting::Theme,
+    Setting::Mode,
+    Setting::Words,
+    Setting::Time,
+    Setting::WordList,
+    Setting::Correction,
+    Setting::Pacer,
+    Setting::Keyboard,
+    Setting::HudWpm,
+    Setting::HudAccuracy,
+    Setting::HudTime,
+    Setting::HudProgress,
+];
+
+const MODES: [Mode; 3] = [Mode::Quote, Mode::Words, Mode::Time];
+const WORD_COUNTS: [usize; 4] = [10, 25, 50, 100];
+const TIMES: [u64; 4] = [15, 30, 60, 120];
+const CORRECTIONS: [Correction; 2] = [Correction::Stop, Correction::Advance];
+const PACERS: [u64; 6] = [0, 40, 60, 80, 100, 120];
+
+const PREVIEW_TEXT: &str = "The quick brown fox jumps over the lazy dog.";
+const PREVIEW_TYPED: &str = "The quixk brown";
+
+// The value `step` places along from `current`, wrapping at either end.
+fn cycle<T: Copy + PartialEq>(values: &[T], current: T, step: isize) -> T {
+    let index = values
+        .iter()
+        .position(|value| *value == current)
+        .map_or(0, |index| {
+            (index as isize + step).rem_euclid(values.len() as isize) as usize
+        });
+    values[index]
+}
+
+fn shown(on: bool) -> String {
+    String::from(if on { "shown" } else { "hidden" })
+}
+
+impl Setting {
+    fn name(&self) -> &'static str {
+        match self {
+            Setting::Layout => "layout",
+            Setting::Theme => "theme",
+            Setting::Mode => "mode",
+            Setting::Words => "words",
+            Setting::Time => "time",
+            Setting::WordList => "word list",
+            Setting::Correction => "correction",
+            Setting::Pacer => "pacer",
+            Setting::Keyboard => "keyboard",
+            Setting::HudWpm => "hud wpm",
+            Setting::HudAccuracy => "hud accuracy",
+            Setting::HudTime => "hud time",
+            Setting::HudProgress => "hud progress",
+        }
+    }
+
+    fn value(&self, app: &App) -> String {
+        let options = &app.options;
+        match self {
+            Setting::Layout => options.keyboard.name.clone(),
+            Setting::Theme => options.theme_name.clone(),
+            Setting::Mode => String::from(options.source.mode.name()),
+            Setting::Words => options.source.words.to_string(),
+            Setting::Time => format!("{}s", options.source.time),
+            Setting::WordList => options
+                .source
+                .word_list
+                .as_ref()
+                .map_or_else(|| String::from("built-in quotes"), Quote::get_name),
+            Setting::Correction => String::from(options.correction.name()),
+            Setting::Pacer if options.pacer == 0 => String::from("off"),
+            Setting::Pacer => format!("{} wpm", options.pacer),
+            Setting::Keyboard => shown(options.show_keyboard),
+            Setting::HudWpm => shown(options.hud.wpm),
+            Setting::HudAccuracy => shown(options.hud.accuracy),
+            Setting::HudTime => shown(options.hud.time),
+            Setting::HudProgress => shown(options.hud.progress),
+        }
+    }
+
+    // Applies the change straight away and writes it to the config file.
+    fn change(&self, app: &mut App, step: isize) -> io::Result<()> {
+        let App {
+            options, config, ..
+        } = app;
+        match self {
+            Setting::Layout => {
+                let name = cycle(&LAYOUTS, options.keyboard.name.as_str(), step).to_string();
+                options.keyboard = get_keyboard_layout(Some(&name), options.geometry, false);
+                config.layout = Some(name);
+            }
+            Setting::Theme => {
+                let name = cycle(&THEMES, options.theme_name.as_str(), step).to_string();
+                options.theme = Theme::load(Some(&name));
+                options.theme_name = name.clone();
+                config.theme = Some(name);
+            }
+            Setting::Mode => {
+                options.source.mode = cycle(&MODES, options.source.mode, step);
+                config.mode = options.source.mode;
+            }
+            Setting::Words => {
+                options.source.words = cycle(&WORD_COUNTS, options.source.words, step);
+                config.words = options.source.words;
+            }
+            Setting::Time => {
+                options.source.time = cycle(&TIMES, options.source.time, step);
+                config.time = options.source.time;
+            }
+            Setting::WordList => {
+                let lists: Vec<Option<PathBuf>> = [None]
+                    .into_iter()
+                    .chain(word_lists().into_iter().map(Some))
+                    .collect();
+                let current = lists
+                    .iter()
+                    .position(|list| *list == config.word_list)
+                    .unwrap_or(0);
+                let next = lists
+                    [(current as isize + step).rem_euclid(lists.len() as isize) as usize]
+                    .clone();
+                options.source.word_list = next.as_deref().map(load_text).transpose()?;
+                config.word_list = next;
+            }
+            Setting::Correction => {
+                options.correction = cycle(&CORRECTIONS, options.correction, step);
+                config.correction = options.correction;
+            }
+            Setting::Pacer => {
+                options.pacer = cycle(&PACERS, options.pacer, step);
+                config.pacer = options.pacer;
+            }
+            Setting::Keyboard => {
+                options.show_keyboard = !options.show_keyboard;
+                config.keyboard = options.show_keyboard;
+            }
+            Setting::HudWpm => {
+                options.hud.wpm = !options.hud.wpm;
+                config.hud.wpm = options.hud.wpm;
+            }
+            Setting::HudAccuracy => {
+                options.hud.accuracy = !options.hud.accuracy;
+                config.hud.accuracy = options.hud.accuracy;
+            }
+            Setting::HudTime => {
+                options.hud.time = !options.hud.time;
+                config.hud.time = options.hud.time;
+            }
+            Setting::HudProgress => {
+                options.hud.progress = !options.hud.progress;
+                config.hud.progress = options.hud.progress;
+            }
+        }
+        config.save()
+    }
+}
+
+#[derive(Default)]
+pub struct Settings {
+    selected: usize,
+}
+
+impl Settings {
+    // A race part-way through the sample text, showing every character state.
+    fn preview(options: &Options) -> (RaceEngine, Status) {
+        let shown = Instant::now();
+        let mut engine = RaceEngine::new(PREVIEW_TEXT, shown)
+            .with_afk_timeout(0)
+            .with_correction(options.correction);
+        let mut at = shown;
+        for typed in PREVIEW_TYPED.chars() {
+            at += Duration::from_millis(150);
+            engine.input(Some(typed), at);
+        }
+
+        let now = at + Duration::from_millis(150);
+        engine.tick(now);
+        let mut status = engine.status(now);
+        if options.pacer > 0 {
+            status.pacer = Some(engine.pace(now, options.pacer));
+        }
+        (engine, status)
+    }
+}
+
+impl Screen for Settings {
+    fn draw(&self, frame: &mut Frame, app: &App) {
+        let rows: Vec<(&str, String)> = SETTINGS
+            .iter()
+            .map(|setting| (setting.name(), setting.value(app)))
+            .collect();
+        let (engine, status) = Settings::preview(&app.options);
+        ui::settings(frame, &rows, self.selected, &engine, &status, &app.options);
+    }
+
+    fn handle(&mut self, event: AppEvent, app: &mut App) -> io::Result<Transition> {
+        match pressed(&event) {
+            Some(KeyCode::Esc | KeyCode::Char('q')) => return Ok(Transition::Back),
+            Some(KeyCode::Up) => self.selected = self.selected.saturating_sub(1),
+            Some(KeyCode::Down) => self.selected = (self.selected + 1).min(SETTINGS.len() - 1),
+            Some(KeyCode::Left) => SETTINGS[self.selected].change(app, -1)?,
+            Some(KeyCode::Right | KeyCode::Enter | KeyCode::Char(' ')) => {
+                SETTINGS[self.selected].change(app, 1)?
+            }
+            _ => (),
+        }
+        Ok(Transition::Stay)
+    }
+}
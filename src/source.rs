@@ -0,0 +1,246 @@
+use crate::daily::Daily;
+use crate::deck::Deck;
+use crate::types::{Quote, QUOTES};
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+pub const DEFAULT_WORDS: usize = 25;
+pub const DEFAULT_TIME: u64 = 30;
+
+// Enough words that nobody runs out of text before the clock does.
+pub const WORDS_PER_MINUTE: u64 = 250;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Mode {
+    // One quote, typed to the end.
+    #[default]
+    Quote,
+    // A fixed number of random words.
+    Words,
+    // Random words until the time runs out.
+    Time,
+}
+
+impl Mode {
+    pub fn from_name(name: &str) -> Option<Mode> {
+        match name {
+            "quote" => Some(Mode::Quote),
+            "words" => Some(Mode::Words),
+            "time" => Some(Mode::Time),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Mode::Quote => "quote",
+            Mode::Words => "words",
+            Mode::Time => "time",
+        }
+    }
+}
+
+// Where race texts come from: the built-in quotes, or a custom text.
+#[derive(Clone, Debug)]
+pub struct TextSource {
+    pub mode: Mode,
+    // Always race this quote instead of a random one.
+    pub quote: Option<Quote>,
+    // Text loaded from a file, used as the quote or as the word list.
+    pub custom: Option<Quote>,
+    // The configured word list, only drawn from in words and time mode.
+    pub word_list: Option<Quote>,
+    pub words: usize,
+    pub time: u64,
+    // Deals the built-in quotes without repeats, picked at random each race when None.
+    pub deck: Option<Deck>,
+    // Races the day's shared text instead.
+    pub daily: Option<Daily>,
+}
+
+impl Default for TextSource {
+    fn default() -> TextSource {
+        TextSource {
+            mode: Mode::Quote,
+            quote: None,
+            custom: None,
+            word_list: None,
+            words: DEFAULT_WORDS,
+            time: DEFAULT_TIME,
+            deck: None,
+            daily: None,
+        }
+    }
+}
+
+impl TextSource {
+    pub fn next_text(&mut self) -> Quote {
+        if let Some(text) = self.daily_text() {
+            return text;
+        }
+
+        let mut rng = rand::thread_rng();
+        match self.mode {
+            Mode::Quote => match self.quote.clone().or_else(|| self.custom.clone()) {
+                Some(quote) => quote,
+                None => {
+                    let index = match &mut self.deck {
+                        Some(deck) => deck.deal(),
+                        None => rng.gen_range(0..QUOTES.len()),
+                    };
+                    let (name, text) = QUOTES[index];
+                    Quote::new(String::from(name), String::from(text))
+                }
+            },
+            Mode::Words => Quote::new(
+                format!("{} words", self.words),
+                random_words(&self.corpus(), self.words, &mut rng),
+            ),
+            Mode::Time => Quote::new(
+                format!("{} seconds", self.time),
+                random_words(
+                    &self.corpus(),
+                    (self.time * WORDS_PER_MINUTE).div_ceil(60) as usize,
+                    &mut rng,
+                ),
+            ),
+        }
+    }
+
+    // The day's text: a built-in quote or a default-length set of words from them, chosen by the
+    // seed alone. Mode, lengths, custom texts and picked quotes are all left out so that everyone
+    // racing the same day and team gets the same text, retries and skips included.
+    pub fn daily_text(&self) -> Option<Quote> {
+        let mut rng = StdRng::seed_from_u64(self.daily.as_ref()?.seed());
+        let index = rng.gen_range(0..=QUOTES.len());
+        Some(match QUOTES.get(index) {
+            Some((name, text)) => Quote::new(String::from(*name), String::from(*text)),
+            None => Quote::new(
+                format!("{DEFAULT_WORDS} words"),
+                random_words(&builtin_corpus(), DEFAULT_WORDS, &mut rng),
+            ),
+        })
+    }
+
+    // Whether `quote` is the day's text, so racing it counts towards the daily challenge.
+    pub fn is_daily(&self, quote: &Quote) -> bool {
+        self.daily_text()
+            .is_some_and(|text| text.get_text() == quote.get_text())
+    }
+
+    // How long a race may run, in nanoseconds. The daily text is raced to the end.
+    pub fn limit(&self) -> Option<u128> {
+        (self.mode == Mode::Time && self.daily.is_none())
+            .then_some(self.time as u128 * 1_000_000_000)
+    }
+
+    fn corpus(&self) -> String {
+        self.custom
+            .as_ref()
+            .or(self.word_list.as_ref())
+            .map_or_else(builtin_corpus, Quote::get_text)
+    }
+}
+
+fn builtin_corpus() -> String {
+    QUOTES.map(|(_, text)| text).join(" ")
+}
+
+pub fn find_quote(name: &str) -> Option<Quote> {
+    QUOTES
+        .iter()
+        .find(|(quote, _)| quote.eq_ignore_ascii_case(name))
+        .map(|(name, text)| Quote::new(String::from(*name), String::from(*text)))
+}
+
+// A text file as one quote named after the file. Line breaks can't be typed, so the text is
+// raced as one line.
+pub fn load_text(path: &Path) -> io::Result<Quote> {
+    let text = fs::read_to_string(path)?;
+    let name = path.file_name().map_or_else(
+        || path.display().to_string(),
+        |name| name.to_string_lossy().into_owned(),
+    );
+    Ok(Quote::new(
+        name,
+        text.split_whitespace().collect::<Vec<&str>>().join(" "),
+    ))
+}
+
+// `count` words drawn at random from `corpus`.
+pub fn random_words(corpus: &str, count: usize, rng: &mut impl Rng) -> String {
+    let words: Vec<&str> = corpus.split_whitespace().collect();
+
+    (0..count)
+        .filter_map(|_| words.choose(rng).copied())
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn word_list() -> Option<Quote> {
+        Some(Quote::new(String::from("list"), String::from("alpha beta")))
+    }
+
+    #[test]
+    fn word_list_is_only_for_word_modes() {
+        let mut source = TextSource {
+            word_list: word_list(),
+            ..TextSource::default()
+        };
+        let quote = source.next_text();
+        assert!(QUOTES.iter().any(|(_, text)| *text == quote.get_text()));
+
+        source.mode = Mode::Words;
+        let words = source.next_text().get_text();
+        assert_eq!(words.split_whitespace().count(), DEFAULT_WORDS);
+        assert!(words
+            .split_whitespace()
+            .all(|word| ["alpha", "beta"].contains(&word)));
+    }
+
+    #[test]
+    fn custom_text_is_the_quote_and_beats_the_word_list() {
+        let custom = Quote::new(String::from("file"), String::from("gamma delta"));
+        let mut source = TextSource {
+            custom: Some(custom.clone()),
+            word_list: word_list(),
+            ..TextSource::default()
+        };
+        assert_eq!(source.next_text().get_text(), custom.get_text());
+
+        source.mode = Mode::Time;
+        let words = source.next_text().get_text();
+        assert!(words
+            .split_whitespace()
+            .all(|word| ["gamma", "delta"].contains(&word)));
+    }
+
+    #[test]
+    fn daily_text_ignores_everything_but_the_day() {
+        let daily = Some(Daily::today(None));
+        let plain = TextSource {
+            daily: daily.clone(),
+            ..TextSource::default()
+        };
+        let mut custom = TextSource {
+            mode: Mode::Time,
+            quote: find_quote("Her"),
+            custom: word_list(),
+            daily,
+            ..TextSource::default()
+        };
+        let text = plain.daily_text().unwrap();
+        assert!(custom.is_daily(&text));
+        assert_eq!(custom.next_text().get_text(), text.get_text());
+        assert_eq!(custom.limit(), None);
+    }
+}
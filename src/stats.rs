@@ -0,0 +1,331 @@
+use crate::anticheat::Flag;
+use crate::types::{Keypress, Race};
+use crate::verify;
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const STATS_PATH: &str = "stats/stats.json";
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Record {
+    pub user: String,
+    pub quote: String,
+    pub layout: String,
+    pub timestamp: u64,
+    pub wpm: f64,
+    pub raw: f64,
+    pub accuracy: f64,
+    pub consistency: f64,
+    pub burst: f64,
+    pub slowest_word: Option<String>,
+    pub reaction: f64,
+    pub time: f64,
+    #[serde(default)]
+    pub flags: Vec<Flag>,
+    #[serde(default)]
+    pub keystrokes: Vec<Keypress>,
+    #[serde(default)]
+    pub chain: String,
+    #[serde(default)]
+    pub signature: String,
+}
+
+impl Record {
+    pub fn new(race: &Race, user: &str, quote: &str, layout: &str, key: Option<&[u8]>) -> Record {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs());
+
+        let mut record = Record {
+            user: String::from(user),
+            quote: String::from(quote),
+            layout: String::from(layout),
+            timestamp,
+            wpm: race.wpm(),
+            raw: race.raw(),
+            accuracy: race.accuracy(),
+            consistency: race.consistency(),
+            burst: race.burst(),
+            slowest_word: race.slowest_word().map(|(word, _)| word),
+            reaction: race.reaction_time(),
+            time: race.time(),
+            flags: race.flags(),
+            keystrokes: race.keystrokes.clone(),
+            chain: String::new(),
+            signature: String::new(),
+        };
+        match key {
+            Some(key) => verify::sign(&mut record, key),
+            // Still chained, so the keystroke log can be checked without a signature.
+            None => record.chain = verify::chain(&record),
+        }
+        record
+    }
+}
+
+// One finished race with every metric and its per-second splits, for scripts and dashboards.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RaceResult {
+    pub user: String,
+    pub quote: String,
+    pub text: String,
+    pub layout: String,
+    pub timestamp: u64,
+    pub wpm: f64,
+    pub raw: f64,
+    pub accuracy: f64,
+    pub consistency: f64,
+    pub burst: f64,
+    pub slowest_word: Option<String>,
+    pub reaction: f64,
+    pub time: f64,
+    pub errors: u64,
+    pub flags: Vec<Flag>,
+    // [seconds, wpm] pairs, cumulative up to each split.
+    pub wpm_data: Vec<(f64, f64)>,
+    pub raw_data: Vec<(f64, f64)>,
+}
+
+impl RaceResult {
+    pub fn new(race: &Race, record: &Record) -> RaceResult {
+        RaceResult {
+            user: record.user.clone(),
+            quote: record.quote.clone(),
+            text: race.text.clone(),
+            layout: record.layout.clone(),
+            timestamp: record.timestamp,
+            wpm: record.wpm,
+            raw: record.raw,
+            accuracy: record.accuracy,
+            consistency: record.consistency,
+            burst: record.burst,
+            slowest_word: record.slowest_word.clone(),
+            reaction: record.reaction,
+            time: record.time,
+            errors: race.incorrect(),
+            flags: record.flags.clone(),
+            wpm_data: race.wpm_data(),
+            raw_data: race.raw_data(),
+        }
+    }
+}
+
+// Adds `result` to the JSON array in `path`, starting the file if there is none.
+pub fn append_result(path: &Path, result: RaceResult) -> io::Result<()> {
+    let mut results: Vec<RaceResult> = match fs::read_to_string(path) {
+        Ok(contents) if !contents.trim().is_empty() => serde_json::from_str(&contents)?,
+        Ok(_) => Vec::new(),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Vec::new(),
+        Err(error) => return Err(error),
+    };
+    results.push(result);
+    fs::write(path, serde_json::to_string_pretty(&results)? + "\n")
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Metric {
+    Wpm,
+    Accuracy,
+    Consistency,
+    Burst,
+    Reaction,
+}
+
+impl Metric {
+    pub fn display(&self, record: &Record) -> String {
+        match self {
+            Metric::Wpm => format!("{:.0}", record.wpm),
+            Metric::Accuracy => format!("{:.2}%", record.accuracy),
+            Metric::Consistency => format!("{:.0}%", record.consistency),
+            Metric::Burst => format!("{:.0}", record.burst),
+            Metric::Reaction => format!("{:.2}s", record.reaction),
+        }
+    }
+
+    // Reaction time is negated so that a larger value is always better.
+    pub fn value(&self, record: &Record) -> f64 {
+        match self {
+            Metric::Wpm => record.wpm,
+            Metric::Accuracy => record.accuracy,
+            Metric::Consistency => record.consistency,
+            Metric::Burst => record.burst,
+            Metric::Reaction => -record.reaction,
+        }
+    }
+}
+
+pub fn load(path: &Path) -> io::Result<Vec<Record>> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(error) => return Err(error),
+    };
+
+    if contents.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    // One unreadable record, such as an infinite speed saved as null, doesn't lose the rest.
+    let records: Vec<serde_json::Value> = serde_json::from_str(&contents)?;
+    Ok(records
+        .into_iter()
+        .filter_map(|record| serde_json::from_value(record).ok())
+        .collect())
+}
+
+pub fn save(path: &Path, records: &[Record]) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, serde_json::to_string_pretty(records)?)
+}
+
+pub fn append(path: &Path, record: Record) -> io::Result<()> {
+    let mut records = load(path)?;
+    records.push(record);
+    save(path, &records)
+}
+
+// Best unflagged record per user, ordered from best to worst by the given metric.
+pub fn leaderboard(records: &[Record], metric: Metric) -> Vec<&Record> {
+    let mut best: Vec<&Record> = Vec::new();
+    for record in records.iter().filter(|record| record.flags.is_empty()) {
+        match best.iter_mut().find(|other| other.user == record.user) {
+            Some(other) if metric.value(record) > metric.value(other) => *other = record,
+            Some(_) => (),
+            None => best.push(record),
+        }
+    }
+
+    best.sort_by(|a, b| metric.value(b).total_cmp(&metric.value(a)));
+    best
+}
+
+pub fn personal_best<'a>(records: &'a [Record], user: &str, metric: Metric) -> Option<&'a Record> {
+    leaderboard(records, metric)
+        .into_iter()
+        .find(|record| record.user == user)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::process;
+
+    fn record(user: &str, wpm: f64, reaction: f64) -> Record {
+        Record {
+            user: String::from(user),
+            quote: String::from("q"),
+            layout: String::from("qwerty"),
+            timestamp: 0,
+            wpm,
+            raw: wpm,
+            accuracy: 100.0,
+            consistency: 90.0,
+            burst: wpm,
+            slowest_word: None,
+            reaction,
+            time: 30.0,
+            flags: Vec::new(),
+            keystrokes: Vec::new(),
+            chain: String::new(),
+            signature: String::new(),
+        }
+    }
+
+    fn users<'a>(board: &[&'a Record]) -> Vec<&'a str> {
+        board.iter().map(|record| record.user.as_str()).collect()
+    }
+
+    #[test]
+    fn unreadable_records_are_skipped() {
+        let path = env::temp_dir().join(format!("typing-stats-{}.json", process::id()));
+        let good = r#"{"user": "a", "quote": "q", "layout": "qwerty", "timestamp": 1, "wpm": 80.0,
+            "raw": 85.0, "accuracy": 98.0, "consistency": 90.0, "burst": 120.0,
+            "slowest_word": null, "reaction": 0.4, "time": 30.0}"#;
+        let bad = good.replace(r#""wpm": 80.0"#, r#""wpm": null"#);
+        fs::write(&path, format!("[{good}, {bad}, {good}]")).unwrap();
+
+        let records = load(&path);
+        fs::remove_file(&path).unwrap();
+        let records = records.unwrap();
+        assert_eq!(records.len(), 2);
+        assert!(records.iter().all(|record| record.wpm == 80.0));
+    }
+
+    #[test]
+    fn missing_and_empty_files_have_no_records() {
+        let path = env::temp_dir().join(format!("typing-empty-{}.json", process::id()));
+        assert!(load(&path).unwrap().is_empty());
+        fs::write(&path, "  \n").unwrap();
+        let records = load(&path);
+        fs::remove_file(&path).unwrap();
+        assert!(records.unwrap().is_empty());
+    }
+
+    #[test]
+    fn leaderboard_keeps_each_users_best_in_order() {
+        let records = [
+            record("a", 70.0, 0.5),
+            record("b", 90.0, 0.6),
+            record("a", 95.0, 0.7),
+            record("c", 60.0, 0.3),
+            record("b", 80.0, 0.4),
+        ];
+
+        let board = leaderboard(&records, Metric::Wpm);
+        assert_eq!(users(&board), ["a", "b", "c"]);
+        assert_eq!(board[0].wpm, 95.0);
+        assert_eq!(board[1].wpm, 90.0);
+    }
+
+    #[test]
+    fn quicker_reactions_rank_higher() {
+        let records = [
+            record("a", 70.0, 0.5),
+            record("b", 90.0, 0.6),
+            record("a", 95.0, 0.7),
+            record("c", 60.0, 0.3),
+            record("b", 80.0, 0.4),
+        ];
+
+        let board = leaderboard(&records, Metric::Reaction);
+        assert_eq!(users(&board), ["c", "b", "a"]);
+        assert_eq!(board[1].reaction, 0.4);
+        assert_eq!(board[2].reaction, 0.5);
+    }
+
+    #[test]
+    fn personal_best_is_per_user_and_metric() {
+        let records = [record("a", 70.0, 0.5), record("a", 95.0, 0.7)];
+
+        assert_eq!(personal_best(&records, "a", Metric::Wpm).unwrap().wpm, 95.0);
+        assert_eq!(
+            personal_best(&records, "a", Metric::Reaction).unwrap().wpm,
+            70.0
+        );
+        assert!(personal_best(&records, "b", Metric::Wpm).is_none());
+    }
+
+    #[test]
+    fn flagged_records_never_count_as_bests() {
+        let mut pasted = record("a", 300.0, 0.1);
+        pasted.flags = vec![Flag::Paste];
+        let mut scripted = record("b", 250.0, 0.1);
+        scripted.flags = vec![Flag::InhumanTiming, Flag::ZeroVariance];
+        let records = [pasted, scripted, record("a", 80.0, 0.5)];
+
+        let board = leaderboard(&records, Metric::Wpm);
+        assert_eq!(users(&board), ["a"]);
+        assert_eq!(board[0].wpm, 80.0);
+        assert_eq!(
+            personal_best(&records, "a", Metric::Reaction).unwrap().wpm,
+            80.0
+        );
+        assert!(personal_best(&records, "b", Metric::Wpm).is_none());
+    }
+}
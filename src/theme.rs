@@ -0,0 +1,311 @@
+use crate::utils::config_dir;
+
+use ratatui::{prelude::*, symbols::Marker};
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+pub const THEMES: [&str; 5] = ["dark", "light", "solarized", "high-contrast", "colorblind"];
+
+#[derive(Clone, Debug)]
+pub struct Theme {
+    text: Color,
+    title: Color,
+    correct: Color,
+    incorrect: Color,
+    cursor: Color,
+    raw: Color,
+    monochrome: bool,
+}
+
+impl Default for Theme {
+    fn default() -> Theme {
+        Theme {
+            text: Color::White,
+            title: Color::Rgb(100, 149, 237),
+            correct: Color::Rgb(80, 200, 120),
+            incorrect: Color::Red,
+            cursor: Color::Rgb(100, 149, 237),
+            raw: Color::White,
+            monochrome: false,
+        }
+    }
+}
+
+impl Theme {
+    pub fn builtin(name: &str) -> Option<Theme> {
+        let theme = match name {
+            "dark" => Theme::default(),
+            "light" => Theme {
+                text: Color::Black,
+                title: Color::Rgb(0, 70, 160),
+                correct: Color::Rgb(0, 128, 0),
+                incorrect: Color::Rgb(200, 0, 0),
+                cursor: Color::Rgb(150, 190, 255),
+                raw: Color::DarkGray,
+                monochrome: false,
+            },
+            "solarized" => Theme {
+                text: Color::Rgb(131, 148, 150),
+                title: Color::Rgb(38, 139, 210),
+                correct: Color::Rgb(133, 153, 0),
+                incorrect: Color::Rgb(220, 50, 47),
+                cursor: Color::Rgb(42, 161, 152),
+                raw: Color::Rgb(181, 137, 0),
+                monochrome: false,
+            },
+            "high-contrast" => Theme {
+                text: Color::White,
+                title: Color::Yellow,
+                correct: Color::LightGreen,
+                incorrect: Color::LightRed,
+                cursor: Color::Magenta,
+                raw: Color::LightCyan,
+                monochrome: false,
+            },
+            "colorblind" => Theme {
+                text: Color::White,
+                title: Color::Rgb(86, 180, 233),
+                correct: Color::Rgb(0, 114, 178),
+                incorrect: Color::Rgb(213, 94, 0),
+                cursor: Color::Rgb(204, 121, 167),
+                raw: Color::Rgb(240, 228, 66),
+                monochrome: false,
+            },
+            _ => return None,
+        };
+        Some(theme)
+    }
+
+    // Theme files live in <config>/themes/<name>.theme and hold `key = colour`
+    // lines; any key left out falls back to the dark theme.
+    pub fn from_file(themes: &Path, name: &str) -> Option<Theme> {
+        let path = themes.join(format!("{name}.theme"));
+        let contents = fs::read_to_string(path).ok()?;
+
+        let mut theme = Theme::default();
+
+        for line in contents.lines() {
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let Ok(color) = Color::from_str(value.trim()) else {
+                continue;
+            };
+
+            match key.trim() {
+                "text" => theme.text = color,
+                "title" => theme.title = color,
+                "correct" => theme.correct = color,
+                "incorrect" => theme.incorrect = color,
+                "cursor" => theme.cursor = color,
+                "raw" => theme.raw = color,
+                _ => (),
+            }
+        }
+
+        Some(theme)
+    }
+
+    pub fn monochrome() -> Theme {
+        Theme {
+            text: Color::Reset,
+            title: Color::Reset,
+            correct: Color::Reset,
+            incorrect: Color::Reset,
+            cursor: Color::Reset,
+            raw: Color::Reset,
+            monochrome: true,
+        }
+    }
+
+    pub fn load(name: Option<&String>) -> Theme {
+        let no_color = env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+        let themes = config_dir().map(|dir| dir.join("themes"));
+        Theme::select(name, no_color, themes.as_deref())
+    }
+
+    fn select(name: Option<&String>, no_color: bool, themes: Option<&Path>) -> Theme {
+        if no_color {
+            return Theme::monochrome();
+        }
+
+        name.and_then(|name| {
+            Theme::builtin(name)
+                .or_else(|| themes.and_then(|themes| Theme::from_file(themes, name)))
+        })
+        .unwrap_or_default()
+    }
+
+    pub fn text(&self) -> Style {
+        Style::default().fg(self.text)
+    }
+
+    pub fn title(&self) -> Style {
+        Style::default().add_modifier(Modifier::BOLD).fg(self.title)
+    }
+
+    pub fn correct(&self) -> Style {
+        if self.monochrome {
+            return Style::default().add_modifier(Modifier::UNDERLINED);
+        }
+        Style::default().fg(self.correct)
+    }
+
+    pub fn incorrect(&self) -> Style {
+        if self.monochrome {
+            return Style::default().add_modifier(Modifier::CROSSED_OUT);
+        }
+        Style::default().bg(self.incorrect)
+    }
+
+    pub fn cursor(&self) -> Style {
+        if self.monochrome {
+            return Style::default().add_modifier(Modifier::REVERSED);
+        }
+        Style::default().bg(self.cursor)
+    }
+
+    pub fn highlight(&self) -> Style {
+        if self.monochrome {
+            return Style::default().add_modifier(Modifier::REVERSED);
+        }
+        Style::default().fg(self.correct)
+    }
+
+    pub fn wpm(&self) -> Style {
+        Style::default().fg(self.correct)
+    }
+
+    pub fn raw(&self) -> Style {
+        Style::default().fg(self.raw)
+    }
+
+    pub fn error(&self) -> Style {
+        Style::default().fg(self.incorrect)
+    }
+
+    pub fn raw_marker(&self) -> Marker {
+        if self.monochrome {
+            return Marker::Dot;
+        }
+        Marker::Braille
+    }
+
+    // Monochrome lines and error dots would both be plain dots, so errors get bars instead.
+    pub fn error_marker(&self) -> Marker {
+        if self.monochrome {
+            return Marker::Bar;
+        }
+        Marker::Dot
+    }
+
+    pub fn heat(&self, heat: f64) -> Style {
+        if self.monochrome {
+            let modifier = match heat {
+                h if h <= 0.0 => Modifier::empty(),
+                h if h < 0.33 => Modifier::DIM,
+                h if h < 0.66 => Modifier::BOLD,
+                _ => Modifier::REVERSED,
+            };
+            return Style::default().add_modifier(modifier);
+        }
+
+        let color = match heat {
+            h if h <= 0.0 => self.text,
+            h if h < 0.33 => self.correct,
+            h if h < 0.66 => self.title,
+            _ => self.incorrect,
+        };
+        Style::default().fg(color)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn name(name: &str) -> Option<String> {
+        Some(name.to_string())
+    }
+
+    #[test]
+    fn every_listed_theme_is_built_in() {
+        for name in THEMES {
+            assert!(Theme::builtin(name).is_some(), "{name}");
+        }
+        assert!(Theme::builtin("neon").is_none());
+    }
+
+    #[test]
+    fn unknown_or_missing_names_fall_back_to_dark() {
+        let dark = Theme::default();
+
+        for theme in [
+            Theme::select(None, false, None),
+            Theme::select(name("neon").as_ref(), false, None),
+        ] {
+            assert_eq!(theme.text, dark.text);
+            assert_eq!(theme.correct, dark.correct);
+            assert!(!theme.monochrome);
+        }
+    }
+
+    #[test]
+    fn no_color_overrides_the_chosen_theme() {
+        let theme = Theme::select(name("solarized").as_ref(), true, None);
+
+        assert!(theme.monochrome);
+        assert_eq!(
+            theme.correct(),
+            Style::default().add_modifier(Modifier::UNDERLINED)
+        );
+        assert_eq!(
+            theme.incorrect(),
+            Style::default().add_modifier(Modifier::CROSSED_OUT)
+        );
+        assert_eq!(
+            theme.cursor(),
+            Style::default().add_modifier(Modifier::REVERSED)
+        );
+        assert_eq!(
+            theme.highlight(),
+            Style::default().add_modifier(Modifier::REVERSED)
+        );
+        assert_ne!(theme.raw_marker(), theme.error_marker());
+    }
+
+    #[test]
+    fn colours_come_from_the_theme() {
+        let light = Theme::select(name("light").as_ref(), false, None);
+
+        assert_eq!(light.text(), Style::default().fg(Color::Black));
+        assert_eq!(
+            light.incorrect(),
+            Style::default().bg(Color::Rgb(200, 0, 0))
+        );
+        assert_eq!(light.raw_marker(), Marker::Braille);
+    }
+
+    #[test]
+    fn theme_files_fill_gaps_from_dark() {
+        let dir = env::temp_dir().join(format!("typing-themes-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("mine.theme"),
+            "text = black\nincorrect = #ff8800\nnot a colour line\ncursor = nonsense\n",
+        )
+        .unwrap();
+
+        let theme = Theme::select(name("mine").as_ref(), false, Some(&dir));
+        let missing = Theme::select(name("yours").as_ref(), false, Some(&dir));
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(theme.text, Color::Black);
+        assert_eq!(theme.incorrect, Color::Rgb(255, 136, 0));
+        assert_eq!(theme.cursor, Theme::default().cursor);
+        assert_eq!(theme.title, Theme::default().title);
+        assert_eq!(missing.text, Theme::default().text);
+    }
+}
@@ -1,7 +1,71 @@
-use ratatui::prelude::*;
+use crate::anticheat::{self, Flag};
+use crate::stats::Record;
+use crate::utils::base_key;
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Instant;
+
+pub const KEY_HEIGHT: u16 = 3;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Geometry {
+    #[default]
+    Ansi,
+    Iso,
+    Ortho,
+    Columnar,
+    Split,
+}
+
+impl Geometry {
+    pub fn from_name(name: &str) -> Option<Geometry> {
+        match name {
+            "ansi" => Some(Geometry::Ansi),
+            "iso" => Some(Geometry::Iso),
+            "ortho" | "ortholinear" => Some(Geometry::Ortho),
+            "columnar" => Some(Geometry::Columnar),
+            "split" => Some(Geometry::Split),
+            _ => None,
+        }
+    }
+}
+
+// x and width are in quarters of a key, y is in terminal lines
+pub struct Key {
+    pub label: String,
+    pub legend: String,
+    pub x: u16,
+    pub y: u16,
+    pub width: u16,
+}
 
 pub struct KeyboardLayout {
+    pub name: String,
     pub rows: [[String; 10]; 3],
+    pub keys: Vec<Key>,
+}
+
+impl KeyboardLayout {
+    pub fn units(&self) -> u16 {
+        self.keys
+            .iter()
+            .map(|key| key.x + key.width)
+            .max()
+            .unwrap_or(0)
+    }
+
+    pub fn width(&self) -> u16 {
+        self.units() * 3 / 2
+    }
+
+    pub fn height(&self) -> u16 {
+        self.keys
+            .iter()
+            .map(|key| key.y + KEY_HEIGHT)
+            .max()
+            .unwrap_or(0)
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -28,6 +92,10 @@ impl Split {
         self.time as f64 / 1e9
     }
 
+    pub fn nanos(&self) -> u128 {
+        self.time
+    }
+
     pub fn wpm(&self) -> f64 {
         self.words(self.hits) / self.minutes()
     }
@@ -37,17 +105,188 @@ impl Split {
     }
 }
 
+#[derive(Clone, Copy, Debug)]
+pub struct Hud {
+    pub wpm: bool,
+    pub accuracy: bool,
+    pub time: bool,
+    pub progress: bool,
+}
+
+impl Default for Hud {
+    fn default() -> Hud {
+        Hud {
+            wpm: true,
+            accuracy: true,
+            time: true,
+            progress: true,
+        }
+    }
+}
+
+impl Hud {
+    pub fn from_spec(spec: &str) -> Hud {
+        let elements: Vec<&str> = spec.split(',').map(str::trim).collect();
+        Hud {
+            wpm: elements.contains(&"wpm"),
+            accuracy: elements.contains(&"acc"),
+            time: elements.contains(&"time"),
+            progress: elements.contains(&"progress"),
+        }
+    }
+
+    pub fn spec(&self) -> String {
+        [
+            (self.wpm, "wpm"),
+            (self.accuracy, "acc"),
+            (self.time, "time"),
+            (self.progress, "progress"),
+        ]
+        .into_iter()
+        .filter_map(|(shown, name)| shown.then_some(name))
+        .collect::<Vec<&str>>()
+        .join(",")
+    }
+
+    pub fn is_empty(&self) -> bool {
+        !(self.wpm || self.accuracy || self.time || self.progress)
+    }
+}
+
+pub struct Status {
+    pub paused: bool,
+    pub pacer: Option<usize>,
+    pub position: usize,
+    pub next_char: String,
+    pub wpm: Option<f64>,
+    pub accuracy: f64,
+    pub time: f64,
+    pub progress: f64,
+}
+
+impl Status {
+    pub fn new(
+        split: Option<&Split>,
+        hits: u64,
+        misses: u64,
+        elapsed: u128,
+        position: usize,
+        length: usize,
+        next_char: String,
+    ) -> Status {
+        let accuracy = if hits + misses == 0 {
+            100.0
+        } else {
+            hits as f64 / (hits + misses) as f64 * 100.0
+        };
+
+        Status {
+            paused: false,
+            pacer: None,
+            position,
+            next_char,
+            wpm: split.map(Split::wpm),
+            accuracy,
+            time: elapsed as f64 / 1e9,
+            progress: position as f64 / length.max(1) as f64,
+        }
+    }
+}
+
+// Race clock that starts on the first keystroke and leaves paused time out of `elapsed`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Clock {
+    start: Option<Instant>,
+    paused: Option<Instant>,
+    idle: u128,
+}
+
+impl Clock {
+    pub fn start_at(&mut self, at: Instant) {
+        self.start.get_or_insert(at);
+    }
+
+    pub fn started(&self) -> Option<Instant> {
+        self.start
+    }
+
+    pub fn elapsed_at(&self, at: Instant) -> u128 {
+        let at = self.paused.map_or(at, |paused| paused.min(at));
+        self.start.map_or(0, |start| {
+            at.saturating_duration_since(start)
+                .as_nanos()
+                .saturating_sub(self.idle)
+        })
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.is_some()
+    }
+
+    // Pauses as of the given moment, which may lie in the past so idle time is not counted, but
+    // never before the clock started.
+    pub fn pause(&mut self, at: Instant) {
+        if let (Some(start), None) = (self.start, self.paused) {
+            self.paused = Some(at.max(start));
+        }
+    }
+
+    pub fn resume(&mut self, at: Instant) {
+        if let Some(paused) = self.paused.take() {
+            self.idle += at.saturating_duration_since(paused).as_nanos();
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Keypress {
+    pub time: u128,
+    pub position: usize,
+    pub typed: Option<char>,
+    pub correct: bool,
+}
+
+// One split interval of a race, from `start` to `time` seconds in.
+pub struct Interval {
+    pub start: f64,
+    pub time: f64,
+    pub wpm: f64,
+    pub errors: u64,
+    pub typed: Vec<Keypress>,
+}
+
 #[derive(Clone, Debug)]
 pub struct Race {
     pub length: u64,
     misses: u64,
     pub time: u128,
     pub splits: Vec<Split>,
+    pub text: String,
+    pub keystrokes: Vec<Keypress>,
+    pub reaction: u128,
+    pub pasted: bool,
 }
 
 impl Race {
-    pub fn new(length: u64, misses: u64, time: u128, splits: Vec<Split>) -> Race {
-        Race { length, misses, time, splits } 
+    pub fn new(
+        length: u64,
+        misses: u64,
+        time: u128,
+        splits: Vec<Split>,
+        text: String,
+        keystrokes: Vec<Keypress>,
+        reaction: u128,
+    ) -> Race {
+        Race {
+            length,
+            misses,
+            time,
+            splits,
+            text,
+            keystrokes,
+            reaction,
+            pasted: false,
+        }
     }
 
     pub fn time(&self) -> f64 {
@@ -63,15 +302,22 @@ impl Race {
     }
 
     pub fn raw(&self) -> f64 {
-        self.words(self.length + self.misses) / self.time()
+        self.words(self.length + self.misses) / self.minutes()
     }
-    
+
     pub fn wpm(&self) -> f64 {
         self.words(self.length) / self.minutes()
     }
 
+    // Whether the race lasted long enough to score. A race over in one keystroke takes no time at
+    // all, and its speeds would be infinite.
+    pub fn is_scorable(&self, split_interval: u128) -> bool {
+        self.time >= split_interval.max(1)
+    }
+
     pub fn wpm_data(&self) -> Vec<(f64, f64)> {
-        let mut splits: Vec<(f64, f64)> = self.splits
+        let mut splits: Vec<(f64, f64)> = self
+            .splits
             .iter()
             .map(|split| (split.time(), split.wpm()))
             .collect();
@@ -80,19 +326,388 @@ impl Race {
     }
 
     pub fn raw_data(&self) -> Vec<(f64, f64)> {
-        let mut splits: Vec<(f64, f64)> = self.splits
+        let mut splits: Vec<(f64, f64)> = self
+            .splits
             .iter()
             .map(|split| (split.time(), split.raw()))
             .collect();
         splits.push((self.time(), self.raw()));
-        return splits
+        splits
+    }
+
+    // Per-split wpm and raw, each averaged over the last `window` splits.
+    pub fn instant_data(&self, window: usize) -> (Series, Series) {
+        let intervals = self.intervals();
+        let averaged: Vec<(f64, Split)> = intervals
+            .iter()
+            .enumerate()
+            .map(|(index, (time, _))| {
+                let from = (index + 1).saturating_sub(window.max(1));
+                let total = intervals[from..=index].iter().fold(
+                    Split::new(0, 0, 0),
+                    |total, (_, interval)| {
+                        Split::new(
+                            total.hits + interval.hits,
+                            total.misses + interval.misses,
+                            total.time + interval.time,
+                        )
+                    },
+                );
+                (*time, total)
+            })
+            .collect();
+
+        (
+            averaged
+                .iter()
+                .map(|(time, split)| (*time, split.wpm()))
+                .collect(),
+            averaged
+                .iter()
+                .map(|(time, split)| (*time, split.raw()))
+                .collect(),
+        )
+    }
+
+    pub fn chart_data(&self, mode: ChartMode) -> (Series, Series) {
+        match mode {
+            ChartMode::Cumulative => (self.wpm_data(), self.raw_data()),
+            ChartMode::Instantaneous => self.instant_data(1),
+            ChartMode::Smoothed => self.instant_data(SMOOTHING_WINDOW),
+        }
+    }
+
+    pub fn errors_data(&self) -> Vec<(f64, f64)> {
+        self.intervals()
+            .iter()
+            .map(|(time, interval)| (*time, interval.misses as f64))
+            .collect()
+    }
+
+    pub fn interval_count(&self) -> usize {
+        self.intervals().len()
+    }
+
+    pub fn interval(&self, index: usize, mode: ChartMode) -> Option<Interval> {
+        let intervals = self.intervals();
+        let (time, interval) = intervals.get(index)?;
+        let end = (*time * 1e9) as u128;
+        let start = end.saturating_sub(interval.time);
+
+        Some(Interval {
+            start: start as f64 / 1e9,
+            time: *time,
+            wpm: self
+                .chart_data(mode)
+                .0
+                .get(index)
+                .map_or(0.0, |(_, wpm)| *wpm),
+            errors: interval.misses,
+            typed: self
+                .keystrokes
+                .iter()
+                .filter(|keypress| keypress.time >= start && keypress.time < end)
+                .cloned()
+                .collect(),
+        })
+    }
+
+    pub fn errors(&self) -> Vec<usize> {
+        self.keystrokes
+            .iter()
+            .filter(|keypress| !keypress.correct)
+            .map(|keypress| keypress.position)
+            .collect()
     }
 
     pub fn accuracy(&self) -> f64 {
         self.length as f64 / (self.length + self.misses) as f64 * 100.0
     }
+
+    pub fn incorrect(&self) -> u64 {
+        self.misses
+    }
+
+    // Wrong keys pressed where the text wanted a space, typing on past the end of a word.
+    pub fn extra(&self) -> u64 {
+        let text: Vec<char> = self.text.chars().collect();
+        self.keystrokes
+            .iter()
+            .filter(|keypress| !keypress.correct && text.get(keypress.position) == Some(&' '))
+            .count() as u64
+    }
+
+    // Characters a wrong key skipped over, so they were left behind without ever being typed
+    // right.
+    pub fn missed(&self) -> u64 {
+        let mut missed: Vec<usize> = self.errors();
+        missed.sort_unstable();
+        missed.dedup();
+        missed
+            .iter()
+            .filter(|&&position| {
+                let passed = self.keystrokes.iter().any(|k| k.position > position);
+                let typed = self
+                    .keystrokes
+                    .iter()
+                    .any(|k| k.correct && k.position == position);
+                passed && !typed
+            })
+            .count() as u64
+    }
+
+    // Pairs the end of each split with the hits, misses and time since the previous one.
+    fn intervals(&self) -> Vec<(f64, Split)> {
+        let end = Split::new(self.length, self.misses, self.time);
+        let mut last = Split::new(0, 0, 0);
+        let mut intervals = Vec::new();
+
+        for split in self.splits.iter().chain([&end]) {
+            if split.time <= last.time {
+                continue;
+            }
+            let interval = Split::new(
+                split.hits - last.hits,
+                split.misses - last.misses,
+                split.time - last.time,
+            );
+            intervals.push((split.time(), interval));
+            last = split.clone();
+        }
+
+        intervals
+    }
+
+    pub fn variation(&self) -> f64 {
+        let wpm: Vec<f64> = self
+            .intervals()
+            .iter()
+            .map(|(_, interval)| interval.wpm())
+            .collect();
+
+        if wpm.is_empty() {
+            return 0.0;
+        }
+        let mean = wpm.iter().sum::<f64>() / wpm.len() as f64;
+        if mean == 0.0 {
+            return 0.0;
+        }
+        let variance = wpm.iter().map(|w| (w - mean).powi(2)).sum::<f64>() / wpm.len() as f64;
+        variance.sqrt() / mean
+    }
+
+    pub fn consistency(&self) -> f64 {
+        (100.0 - self.variation() * 100.0).max(0.0)
+    }
+
+    pub fn burst(&self) -> f64 {
+        let window = BURST_WINDOW as f64 / 1e9;
+        if self.time() <= window {
+            return self.wpm();
+        }
+
+        let times: Vec<u128> = self
+            .keystrokes
+            .iter()
+            .filter(|keypress| keypress.correct)
+            .map(|keypress| keypress.time)
+            .collect();
+
+        let mut best = 0;
+        let mut first = 0;
+        for (last, time) in times.iter().enumerate() {
+            while *time - times[first] > BURST_WINDOW {
+                first += 1;
+            }
+            best = best.max(last - first + 1);
+        }
+
+        self.words(best as u64) / (window / 60.0)
+    }
+
+    pub fn slowest_word(&self) -> Option<(String, f64)> {
+        let chars: Vec<char> = self.text.chars().collect();
+        let mut finished = vec![0; chars.len()];
+        for keypress in self.keystrokes.iter().filter(|keypress| keypress.correct) {
+            if let Some(time) = finished.get_mut(keypress.position) {
+                *time = keypress.time;
+            }
+        }
+
+        let mut slowest: Option<(String, f64)> = None;
+        let mut start = 0;
+        let mut previous = 0;
+        for (end, c) in chars.iter().chain([&' ']).enumerate() {
+            if *c != ' ' {
+                continue;
+            }
+            if end > start {
+                let time = finished[end - 1].saturating_sub(previous);
+                let word = Split::new(end as u64 - start as u64, 0, time.max(1));
+                if slowest.as_ref().is_none_or(|(_, wpm)| word.wpm() < *wpm) {
+                    slowest = Some((chars[start..end].iter().collect(), word.wpm()));
+                }
+                previous = finished[end.min(chars.len() - 1)];
+            }
+            start = end + 1;
+        }
+
+        slowest
+    }
+
+    pub fn flags(&self) -> Vec<Flag> {
+        let mut flags = Vec::new();
+        if self.pasted {
+            flags.push(Flag::Paste);
+        }
+        flags.extend(anticheat::timing_flags(&self.keystrokes));
+        if self.keystrokes.len() < anticheat::MIN_KEYSTROKES {
+            flags.push(Flag::TooShort);
+        }
+        flags
+    }
+
+    pub fn reaction_time(&self) -> f64 {
+        self.reaction as f64 / 1e9
+    }
+
+    pub fn mistakes(&self) -> Vec<(String, usize)> {
+        let chars: Vec<char> = self.text.chars().collect();
+        let errors = self.errors();
+        let mut mistakes = Vec::new();
+        let mut start = 0;
+
+        for (end, c) in chars.iter().chain([&' ']).enumerate() {
+            if *c != ' ' {
+                continue;
+            }
+            let count = errors
+                .iter()
+                .filter(|&&error| error >= start && error < end)
+                .count();
+            if count > 0 && end > start {
+                mistakes.push((chars[start..end].iter().collect(), count));
+            }
+            start = end + 1;
+        }
+
+        mistakes
+    }
+
+    pub fn key_errors(&self) -> HashMap<String, u64> {
+        let chars: Vec<char> = self.text.chars().collect();
+        let mut keys = HashMap::new();
+
+        for error in self.errors() {
+            if let Some(&c) = chars.get(error) {
+                *keys.entry(base_key(c)).or_insert(0) += 1;
+            }
+        }
+
+        keys
+    }
+}
+
+#[derive(Default)]
+pub struct Session {
+    pub total_words: f64,
+    pub total_time: f64,
+    pub last_race: Option<Race>,
+    pub history: Vec<Record>,
+    // None when the install's signing key can't be read or made, so records go unsigned.
+    pub key: Option<Vec<u8>>,
+}
+
+impl Session {
+    pub fn add(&mut self, race: Race) {
+        self.total_words += race.words(race.length);
+        self.total_time += race.minutes();
+        self.last_race = Some(race);
+    }
+
+    pub fn average(&self) -> Option<f64> {
+        if self.total_words > 0.0 {
+            Some(self.total_words / self.total_time)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ResultsTab {
+    #[default]
+    Summary,
+    Chart,
+    Mistakes,
+    Keys,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ChartMode {
+    #[default]
+    Cumulative,
+    Instantaneous,
+    Smoothed,
+}
+
+impl ChartMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ChartMode::Cumulative => "cumulative",
+            ChartMode::Instantaneous => "instantaneous",
+            ChartMode::Smoothed => "smoothed",
+        }
+    }
+
+    pub fn next(&self) -> ChartMode {
+        match self {
+            ChartMode::Cumulative => ChartMode::Instantaneous,
+            ChartMode::Instantaneous => ChartMode::Smoothed,
+            ChartMode::Smoothed => ChartMode::Cumulative,
+        }
+    }
 }
 
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ResultsView {
+    pub tab: ResultsTab,
+    // The split interval inspected on the chart.
+    pub interval: usize,
+    pub mode: ChartMode,
+}
+
+impl ResultsTab {
+    pub const ALL: [ResultsTab; 4] = [
+        ResultsTab::Summary,
+        ResultsTab::Chart,
+        ResultsTab::Mistakes,
+        ResultsTab::Keys,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ResultsTab::Summary => "summary",
+            ResultsTab::Chart => "chart",
+            ResultsTab::Mistakes => "mistakes",
+            ResultsTab::Keys => "keys",
+        }
+    }
+
+    pub fn index(&self) -> usize {
+        ResultsTab::ALL.iter().position(|tab| tab == self).unwrap()
+    }
+
+    pub fn next(&self) -> ResultsTab {
+        ResultsTab::ALL[(self.index() + 1) % ResultsTab::ALL.len()]
+    }
+
+    pub fn previous(&self) -> ResultsTab {
+        ResultsTab::ALL[(self.index() + ResultsTab::ALL.len() - 1) % ResultsTab::ALL.len()]
+    }
+}
+
+#[derive(Clone, Debug)]
 pub struct Quote {
     name: String,
     text: String,
@@ -106,22 +721,32 @@ impl Quote {
     pub fn get_text(&self) -> String {
         self.text.clone()
     }
-    
+
     pub fn new(name: String, text: String) -> Quote {
         Quote { name, text }
     }
 }
 
 pub enum Keystroke {
-    Wrong,
+    Wrong(Option<char>),
     Correct,
+    Paste,
+    Pause,
+    Tab,
+    Enter,
+    Skip,
     Quit,
     Invalid,
 }
 
-pub const CORRECT: Color = Color::Rgb(80, 200, 120);
-pub const INCORRECT: Color = Color::Red;
-pub const TITLE: Color = Color::Rgb(100, 149, 237);
+pub type Series = Vec<(f64, f64)>;
+
+pub const BURST_WINDOW: u128 = 5_000_000_000;
+pub const SPLIT_INTERVAL: u128 = 1_000_000_000;
+pub const SMOOTHING_WINDOW: usize = 3;
+pub const AFK_TIMEOUT: u128 = 10_000_000_000;
+
+pub const LAYOUTS: [&str; 3] = ["qwerty", "colemak", "dvorak"];
 
 pub const QUOTES: [(&str, &str); 10] = [
         ("Raising Smart Kids for Dummies", "The sooner your kids appreciate the value of work, the more successful they will be. Work is part of life. You work to earn money, put food on the table, and keep your homes orderly and clean. For your kids, work involves schoolwork, homework, and teamwork at home and in the community."),
@@ -136,7 +761,6 @@ pub const QUOTES: [(&str, &str); 10] = [
         ("Avengers: Infinity War", "With all six stones, I could simply snap my fingers, they would all cease to exist and I call that... mercy. And then what? I finally rest, and watch the sun rise on a grateful universe. The hardest choices require the strongest wills.")
     ];
 
-
 pub const ASCII_ART_1: &str = r#"
 $$$$$$$$\                                
 \__$$  __|                               
@@ -161,4 +785,3 @@ $$$$$$$$\
        \$$$$$$  |$$ |                          
         \______/ \__|                          
 "#;
-
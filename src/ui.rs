@@ -1,139 +1,457 @@
-use crate::types::{Race, ASCII_ART_1, ASCII_ART_2, TITLE};
+use crate::analyze::{LayoutStats, FINGERS};
+use crate::anticheat;
+use crate::catalog::{Entry, Filter};
+use crate::engine::{CharState, RaceEngine};
+use crate::stats::{self, Metric, Record};
+use crate::theme::Theme;
+use crate::types::{
+    Hud, KeyboardLayout, Quote, Race, ResultsTab, ResultsView, Session, Status, ASCII_ART_1,
+    ASCII_ART_2,
+};
+use crate::utils::wrap_words;
 use ratatui::{prelude::*, widgets::*};
 
-use super::draw_keyboard;
+use super::{draw_heatmap, draw_keyboard, Options};
 
-pub fn title(
-    frame: &mut Frame,
-    last_race: Option<Race>,
-    total_words: f64,
-    total_time: f64,
-    username: &String,
-    show_results: bool,
-) {
-    let areas = Layout::new(
-        Direction::Vertical,
-        [
-            Constraint::Percentage(2),
-            Constraint::Percentage(5),
-            Constraint::Percentage(28),
-            Constraint::Percentage(40),
-            Constraint::Percentage(4),
-            Constraint::Percentage(4),
-            Constraint::Percentage(2),
-            Constraint::Percentage(4),
-        ],
-    )
-    .split(frame.size());
+const ART_WIDTH: u16 = 50;
+const ART_HEIGHT: u16 = 36;
+const COMPACT_WIDTH: u16 = 40;
+const MIN_TEXT_HEIGHT: u16 = 10;
+const VISIBLE_LINES: u16 = 3;
+const RESULTS_HEIGHT: u16 = 14;
+
+pub fn title(frame: &mut Frame, session: &Session, username: &str, menu: &str, theme: &Theme) {
+    frame.render_widget(Block::new().style(theme.text()), frame.size());
 
-    if last_race.is_none() {
+    let size = frame.size();
+    let compact = size.width < ART_WIDTH || size.height < ART_HEIGHT;
+
+    let areas = if compact {
+        Layout::new(
+            Direction::Vertical,
+            [
+                Constraint::Fill(1),
+                Constraint::Length(1),
+                Constraint::Length(1),
+                Constraint::Length(1),
+                Constraint::Length(1),
+                Constraint::Length(1),
+                Constraint::Length(1),
+                Constraint::Length(1),
+                Constraint::Length(1),
+                Constraint::Fill(1),
+            ],
+        )
+        .split(size)[1..]
+            .to_vec()
+    } else {
+        Layout::new(
+            Direction::Vertical,
+            [
+                Constraint::Percentage(2),
+                Constraint::Percentage(5),
+                Constraint::Percentage(28),
+                Constraint::Percentage(40),
+                Constraint::Percentage(4),
+                Constraint::Percentage(4),
+                Constraint::Percentage(2),
+                Constraint::Percentage(4),
+            ],
+        )
+        .split(size)
+        .to_vec()
+    };
+
+    frame.render_widget(Paragraph::new(menu).alignment(Alignment::Center), areas[1]);
+
+    if compact {
         frame.render_widget(
-            Paragraph::new("(s) start | (q) quit").alignment(Alignment::Center),
-            areas[1],
+            Paragraph::new("Term Typer")
+                .alignment(Alignment::Center)
+                .style(theme.title()),
+            areas[3],
         );
     } else {
         frame.render_widget(
-            Paragraph::new("(s) start | (q) quit | (r) results").alignment(Alignment::Center),
-            areas[1],
+            Paragraph::new(ASCII_ART_1)
+                .alignment(Alignment::Center)
+                .style(theme.text()),
+            areas[2],
         );
-    }
-
-    frame.render_widget(
-        Paragraph::new(ASCII_ART_1)
-            .alignment(Alignment::Center)
-            .style(Style::default().fg(Color::White)),
-        areas[2],
-    );
 
-    frame.render_widget(
-        Paragraph::new(ASCII_ART_2)
-            .alignment(Alignment::Center)
-            .style(Style::default().fg(Color::White)),
-        areas[3],
-    );
+        frame.render_widget(
+            Paragraph::new(ASCII_ART_2)
+                .alignment(Alignment::Center)
+                .style(theme.text()),
+            areas[3],
+        );
+    }
 
     frame.render_widget(
-        Paragraph::new(format!("{username}")).alignment(Alignment::Center),
+        Paragraph::new(username).alignment(Alignment::Center),
         areas[5],
     );
 
-    if total_words > 0.0 {
+    if let Some(average) = session.average() {
         frame.render_widget(
-            Paragraph::new(format!(
-                "Session average: {:.0} wpm",
-                total_words / total_time
-            ))
-            .alignment(Alignment::Center),
+            Paragraph::new(format!("Session average: {:.0} wpm", average))
+                .alignment(Alignment::Center),
             areas[7],
         );
     }
-
-    if let Some(race) = last_race {
-        if show_results {
-            results(frame, race);
-        }
-    }
 }
 
 pub fn race(
     frame: &mut Frame,
-    quote_name: String,
-    paragraph: Paragraph,
-    layout: Option<&String>,
-    next_char: String,
+    size: Rect,
+    quote_name: &str,
+    engine: &RaceEngine,
+    status: &Status,
+    options: &Options,
 ) {
+    let Options {
+        keyboard,
+        theme,
+        hud,
+        show_keyboard,
+        ..
+    } = options;
+    frame.render_widget(Block::new().style(theme.text()), size);
+
+    let keyboard = Some(keyboard).filter(|keyboard| {
+        *show_keyboard
+            && size.width >= keyboard.width()
+            && size.height >= keyboard.height() + MIN_TEXT_HEIGHT
+    });
+    let keyboard_height = keyboard.map_or(0, KeyboardLayout::height);
+
     let areas = Layout::new(
         Direction::Vertical,
         [
             Constraint::Percentage(10),
-            Constraint::Percentage(30),
-            Constraint::Percentage(5),
-            Constraint::Length(12),
-            Constraint::Fill(1),
+            Constraint::Min(MIN_TEXT_HEIGHT - 2),
+            Constraint::Length(1),
+            Constraint::Length(keyboard_height),
+            Constraint::Percentage(10),
         ],
     )
-    .split(frame.size());
+    .split(size);
 
+    let margin = if size.width < COMPACT_WIDTH { 0 } else { 15 };
     let text = Layout::new(
         Direction::Horizontal,
         [
-            Constraint::Percentage(15),
-            Constraint::Percentage(70),
-            Constraint::Percentage(15),
+            Constraint::Percentage(margin),
+            Constraint::Fill(1),
+            Constraint::Percentage(margin),
         ],
     )
     .split(areas[1]);
 
     let textboxes = Layout::new(
         Direction::Vertical,
-        [Constraint::Percentage(20), Constraint::Percentage(80)],
+        [
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Length(VISIBLE_LINES),
+            Constraint::Fill(1),
+        ],
     )
     .split(text[1]);
 
     frame.render_widget(
-        Paragraph::new(format!("## {quote_name}"))
-            .style(Style::default().add_modifier(Modifier::BOLD).fg(TITLE)),
+        Paragraph::new(format!("## {quote_name}")).style(theme.title()),
         textboxes[0],
     );
 
-    frame.render_widget(paragraph.wrap(Wrap { trim: false }).alignment( Alignment::Center ), textboxes[1]);
-    let keyboard_width = 60;
+    if !hud.is_empty() {
+        draw_hud(frame, textboxes[1], hud, status, theme);
+    }
+
+    if status.paused {
+        frame.render_widget(
+            Paragraph::new("paused, press any key to resume")
+                .alignment(Alignment::Center)
+                .style(theme.title()),
+            textboxes[2],
+        );
+    }
+
+    let line = styled_text(engine, status.pacer, theme);
+    text_view(frame, textboxes[3], &line, status.position);
+    frame.render_widget(
+        Paragraph::new("(esc) pause | (tab+enter) restart | (ctrl+n) new quote | (ctrl+c) quit")
+            .alignment(Alignment::Center),
+        areas[4],
+    );
+    if let Some(keyboard) = keyboard {
+        draw_keyboard(
+            frame,
+            &centered_width(keyboard.width(), areas[3]),
+            &status.next_char,
+            keyboard,
+            theme,
+        );
+    }
+}
+
+fn draw_hud(frame: &mut Frame, area: Rect, hud: &Hud, status: &Status, theme: &Theme) {
+    let mut elements = Vec::new();
+    if hud.wpm {
+        elements.push(match status.wpm {
+            Some(wpm) => format!("{:.0} wpm", wpm),
+            None => String::from("-- wpm"),
+        });
+    }
+    if hud.accuracy {
+        elements.push(format!("{:.1}%", status.accuracy));
+    }
+    if hud.time {
+        let seconds = status.time as u64;
+        elements.push(format!("{}:{:02}", seconds / 60, seconds % 60));
+    }
 
-    let split = Layout::new(
+    let areas = Layout::new(
         Direction::Horizontal,
+        [Constraint::Fill(1), Constraint::Percentage(40)],
+    )
+    .split(area);
+
+    frame.render_widget(Paragraph::new(elements.join("  ")), areas[0]);
+
+    if hud.progress {
+        frame.render_widget(
+            LineGauge::default()
+                .ratio(status.progress.clamp(0.0, 1.0))
+                .gauge_style(theme.wpm()),
+            areas[1],
+        );
+    }
+}
+
+fn styled_text(engine: &RaceEngine, pacer: Option<usize>, theme: &Theme) -> Line<'static> {
+    Line::from(
+        engine
+            .text()
+            .iter()
+            .zip(engine.states())
+            .enumerate()
+            .map(|(index, (c, state))| {
+                let mut span = Span::raw(c.to_string());
+                if pacer == Some(index) {
+                    span = span.underlined();
+                }
+                match state {
+                    CharState::Pending => span,
+                    CharState::Cursor => span.patch_style(theme.cursor()),
+                    CharState::Incorrect => span
+                        .patch_style(theme.cursor())
+                        .patch_style(theme.incorrect()),
+                    CharState::Missed => span.patch_style(theme.incorrect()),
+                    CharState::Correct => span.patch_style(theme.correct()),
+                }
+            })
+            .collect::<Vec<Span>>(),
+    )
+}
+
+fn text_view(frame: &mut Frame, area: Rect, line: &Line, position: usize) {
+    let chars: Vec<char> = line
+        .spans
+        .iter()
+        .flat_map(|span| span.content.chars())
+        .collect();
+    let lines = wrap_words(&chars, area.width as usize);
+
+    let current = lines
+        .iter()
+        .position(|range| range.contains(&position))
+        .unwrap_or(lines.len() - 1);
+    let first = current.saturating_sub(1);
+
+    let visible: Vec<Line> = lines
+        .iter()
+        .skip(first)
+        .take(VISIBLE_LINES as usize)
+        .map(|range| Line::from(line.spans[range.clone()].to_vec()))
+        .collect();
+
+    frame.render_widget(Paragraph::new(visible).alignment(Alignment::Center), area);
+}
+
+pub fn results(
+    frame: &mut Frame,
+    race: &Race,
+    view: ResultsView,
+    keyboard: &KeyboardLayout,
+    best: &Best,
+    theme: &Theme,
+) {
+    let height = (keyboard.height() + 4).max(RESULTS_HEIGHT);
+    let area = centered_rect(90, height, frame.size());
+
+    let block = Block::bordered().style(theme.text());
+    let inner = block.inner(area);
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(block, area);
+
+    let areas = Layout::new(
+        Direction::Vertical,
         [
-            Constraint::Min((areas[2].width - keyboard_width) / 2),
-            Constraint::Min(60),
-            Constraint::Min((areas[2].width - keyboard_width) / 2),
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Fill(1),
         ],
     )
-    .split(areas[3]);
+    .split(inner);
+
+    frame.render_widget(
+        Tabs::new(ResultsTab::ALL.map(|tab| tab.name()))
+            .select(view.tab.index())
+            .highlight_style(theme.title()),
+        areas[0],
+    );
 
-    draw_keyboard(frame, &split[1], &next_char, layout);
+    match view.tab {
+        ResultsTab::Summary => summary(frame, areas[2], race, best, theme),
+        ResultsTab::Chart => chart(frame, areas[2], race, view, theme),
+        ResultsTab::Mistakes => mistakes(frame, areas[2], race, theme),
+        ResultsTab::Keys => draw_heatmap(
+            frame,
+            &centered_width(keyboard.width(), areas[2]),
+            keyboard,
+            &race.key_errors(),
+            theme,
+        ),
+    }
+}
+
+pub struct Best<'a> {
+    history: &'a [Record],
+    username: &'a str,
 }
 
-pub fn results(frame: &mut Frame, race: Race) {
-    let area = centered_rect(90, frame.size());
+impl<'a> Best<'a> {
+    pub fn new(history: &'a [Record], username: &'a str) -> Best<'a> {
+        Best { history, username }
+    }
+
+    fn get(&self, metric: Metric) -> String {
+        stats::personal_best(self.history, self.username, metric).map_or(String::new(), |record| {
+            format!("best {}", metric.display(record))
+        })
+    }
+}
+
+fn summary(frame: &mut Frame, area: Rect, race: &Race, best: &Best, theme: &Theme) {
+    let slowest = race
+        .slowest_word()
+        .map_or(String::from("-"), |(word, wpm)| {
+            format!("{word} ({wpm:.0})")
+        });
+
+    let rows = [
+        ("wpm", format!("{:.0}", race.wpm()), Some(Metric::Wpm)),
+        ("raw", format!("{:.0}", race.raw()), None),
+        (
+            "acc",
+            format!("{:.2}%", race.accuracy()),
+            Some(Metric::Accuracy),
+        ),
+        (
+            "consistency",
+            format!("{:.0}%", race.consistency()),
+            Some(Metric::Consistency),
+        ),
+        ("burst", format!("{:.0}", race.burst()), Some(Metric::Burst)),
+        ("slowest word", slowest, None),
+        (
+            "reaction",
+            format!("{:.2}s", race.reaction_time()),
+            Some(Metric::Reaction),
+        ),
+        ("time", format!("{:.1}s", race.time()), None),
+        (
+            "characters",
+            format!(
+                "{}/{}/{}/{}",
+                race.length,
+                race.incorrect() - race.extra(),
+                race.extra(),
+                race.missed()
+            ),
+            None,
+        ),
+    ]
+    .map(|(name, value, metric)| {
+        Row::new([
+            String::from(name),
+            value,
+            metric.map_or(String::new(), |metric| best.get(metric)),
+        ])
+    });
+
+    frame.render_widget(
+        Table::new(
+            rows,
+            [
+                Constraint::Length(13),
+                Constraint::Length(20),
+                Constraint::Length(13),
+            ],
+        ),
+        centered_width(48, area),
+    );
+
+    let flags = race.flags();
+    if !flags.is_empty() && area.height > 0 {
+        frame.render_widget(
+            Paragraph::new(format!(
+                "flagged: {}, excluded from bests",
+                anticheat::describe(&flags)
+            ))
+            .alignment(Alignment::Center)
+            .style(theme.incorrect()),
+            Rect {
+                y: area.bottom() - 1,
+                height: 1,
+                ..area
+            },
+        );
+    }
+}
+
+fn mistakes(frame: &mut Frame, area: Rect, race: &Race, theme: &Theme) {
+    let mistakes = race.mistakes();
+    if mistakes.is_empty() {
+        frame.render_widget(
+            Paragraph::new("no mistakes").alignment(Alignment::Center),
+            area,
+        );
+        return;
+    }
+
+    let words: Vec<Span> = mistakes
+        .into_iter()
+        .flat_map(|(word, count)| {
+            [
+                Span::styled(word, theme.incorrect()),
+                Span::raw(format!(" ×{count}   ")),
+            ]
+        })
+        .collect();
+
+    frame.render_widget(
+        Paragraph::new(Line::from(words))
+            .wrap(Wrap { trim: true })
+            .alignment(Alignment::Center),
+        centered_width(area.width.saturating_sub(4), area),
+    );
+}
+
+fn chart(frame: &mut Frame, area: Rect, race: &Race, view: ResultsView, theme: &Theme) {
     let popup_layout = Layout::new(
         Direction::Horizontal,
         [
@@ -141,7 +459,7 @@ pub fn results(frame: &mut Frame, race: Race) {
             Constraint::Length(5),
             Constraint::Length(4),
             Constraint::Fill(1),
-            Constraint::Length(2),
+            Constraint::Length(4),
         ],
     )
     .split(area);
@@ -160,28 +478,17 @@ pub fn results(frame: &mut Frame, race: Race) {
     )
     .split(popup_layout[1]);
 
-    let block = Block::bordered();
-
-    frame.render_widget(Clear, area);
-    frame.render_widget(block, area);
-
-    let wpm = race.wpm_data();
-    let raw = race.raw_data();
+    let (wpm, raw) = race.chart_data(view.mode);
+    let errors = race.errors_data();
 
-    frame.render_widget(
-        Paragraph::new(format!("wpm",)).alignment(Alignment::Center),
-        stats[1],
-    );
+    frame.render_widget(Paragraph::new("wpm").alignment(Alignment::Center), stats[1]);
 
     frame.render_widget(
         Paragraph::new(format!("{:.0}", f64::round(race.wpm()),)).alignment(Alignment::Center),
         stats[2],
     );
 
-    frame.render_widget(
-        Paragraph::new(format!("acc",)).alignment(Alignment::Center),
-        stats[4],
-    );
+    frame.render_widget(Paragraph::new("acc").alignment(Alignment::Center), stats[4]);
 
     frame.render_widget(
         Paragraph::new(format!("{:.2}%", race.accuracy())).alignment(Alignment::Center),
@@ -191,29 +498,54 @@ pub fn results(frame: &mut Frame, race: Race) {
     let (mut min_first, mut max_first) = (f64::MAX, f64::MIN);
     let (mut min_second, mut max_second) = (f64::MAX, f64::MIN);
 
-    for (first, second) in wpm.iter() {
+    for (first, second) in wpm.iter().chain(raw.iter()) {
         min_first = f64::min(min_first, *first);
         min_second = f64::min(min_second, *second);
         max_first = f64::max(max_first, *first);
         max_second = f64::max(max_second, *second);
     }
+    min_second = f64::min(min_second, 0.0);
+
+    let max_errors = errors.iter().map(|(_, count)| *count).fold(1.0, f64::max);
+    let scale = |count: f64| min_second + count / max_errors * (max_second - min_second);
+    let scaled_errors: Vec<(f64, f64)> = errors
+        .iter()
+        .filter(|(_, count)| *count > 0.0)
+        .map(|(time, count)| (*time, scale(*count)))
+        .collect();
+
+    let inspected = race.interval(view.interval, view.mode);
+    let cursor: Vec<(f64, f64)> = inspected
+        .iter()
+        .map(|interval| (interval.time, interval.wpm))
+        .collect();
 
     let graph = Chart::new(vec![
         Dataset::default()
-            .marker(symbols::Marker::Braille)
+            .marker(theme.raw_marker())
             .graph_type(GraphType::Line)
-            .style(Style::default().white())
+            .style(theme.raw())
             .data(&raw),
         Dataset::default()
             .marker(symbols::Marker::Braille)
             .graph_type(GraphType::Line)
-            .style(Style::default().green())
+            .style(theme.wpm())
             .data(&wpm),
+        Dataset::default()
+            .marker(theme.error_marker())
+            .graph_type(GraphType::Scatter)
+            .style(theme.error())
+            .data(&scaled_errors),
+        Dataset::default()
+            .marker(symbols::Marker::Block)
+            .graph_type(GraphType::Scatter)
+            .style(theme.title())
+            .data(&cursor),
     ])
     .block(Block::new())
     .x_axis(
         Axis::default()
-            .style(Style::default().white())
+            .style(theme.text())
             .bounds([min_first, max_first])
             .labels(vec![
                 format!("{:.0}", min_first).into(),
@@ -222,7 +554,7 @@ pub fn results(frame: &mut Frame, race: Race) {
     )
     .y_axis(
         Axis::default()
-            .style(Style::default().white())
+            .style(theme.text())
             .bounds([min_second, max_second])
             .labels(vec![
                 format!("{:.0}", min_second).into(),
@@ -233,21 +565,379 @@ pub fn results(frame: &mut Frame, race: Race) {
     let graph_layout = Layout::new(
         Direction::Vertical,
         [
-            Constraint::Length(2),
+            Constraint::Length(1),
             Constraint::Fill(1),
-            Constraint::Length(2),
+            Constraint::Length(1),
         ],
     )
     .split(popup_layout[3]);
 
+    frame.render_widget(
+        Paragraph::new(view.mode.name())
+            .alignment(Alignment::Center)
+            .style(theme.title()),
+        graph_layout[0],
+    );
     frame.render_widget(graph, graph_layout[1]);
+
+    let errors_axis = Rect {
+        y: graph_layout[1].y,
+        height: graph_layout[1].height.saturating_sub(2),
+        ..popup_layout[4]
+    };
+    frame.render_widget(
+        Paragraph::new(format!("{:.0}", max_errors)).style(theme.error()),
+        errors_axis,
+    );
+    if errors_axis.height > 1 {
+        frame.render_widget(
+            Paragraph::new("0").style(theme.error()),
+            Rect {
+                y: errors_axis.bottom() - 1,
+                height: 1,
+                ..errors_axis
+            },
+        );
+    }
+
+    if let Some(interval) = inspected {
+        let mut line = vec![Span::raw(format!(
+            "{:.1}-{:.1}s  {:.0} wpm  {} errors  ",
+            interval.start, interval.time, interval.wpm, interval.errors
+        ))];
+        line.extend(interval.typed.iter().map(|keypress| {
+            let typed = keypress.typed.map_or(String::from("�"), String::from);
+            if keypress.correct {
+                Span::raw(typed)
+            } else {
+                Span::styled(typed, theme.incorrect())
+            }
+        }));
+
+        frame.render_widget(
+            Paragraph::new(Line::from(line)).alignment(Alignment::Center),
+            graph_layout[2],
+        );
+    }
 }
 
-fn centered_rect(percent_x: u16, r: Rect) -> Rect {
-    let percent_y = 11;
+pub fn history(frame: &mut Frame, records: &[&Record], selected: usize, theme: &Theme) {
+    frame.render_widget(Block::new().style(theme.text()), frame.size());
+
+    let areas = Layout::new(
+        Direction::Vertical,
+        [
+            Constraint::Length(2),
+            Constraint::Fill(1),
+            Constraint::Length(1),
+        ],
+    )
+    .split(frame.size());
+
+    let header = ["quote", "wpm", "raw", "acc", "time", ""]
+        .into_iter()
+        .collect::<Row>()
+        .style(theme.title());
+
+    let rows = records.iter().map(|record| {
+        Row::new([
+            record.quote.clone(),
+            format!("{:.0}", record.wpm),
+            format!("{:.0}", record.raw),
+            format!("{:.1}%", record.accuracy),
+            format!("{:.1}s", record.time),
+            if record.flags.is_empty() {
+                String::new()
+            } else {
+                String::from("flagged")
+            },
+        ])
+    });
+
+    let widths = [
+        Constraint::Fill(1),
+        Constraint::Length(5),
+        Constraint::Length(5),
+        Constraint::Length(7),
+        Constraint::Length(7),
+        Constraint::Length(8),
+    ];
+
+    let mut state = TableState::default().with_selected(Some(selected));
+    frame.render_stateful_widget(
+        Table::new(rows, widths)
+            .header(header)
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+            .block(Block::bordered().title(format!("history ({} races)", records.len()))),
+        centered_width(86, areas[1]),
+        &mut state,
+    );
+
+    frame.render_widget(
+        Paragraph::new("(↑/↓) scroll | (esc) back").alignment(Alignment::Center),
+        areas[2],
+    );
+}
+
+pub fn lobby(frame: &mut Frame, quote: &Quote, best: Option<f64>, theme: &Theme) {
+    frame.render_widget(Block::new().style(theme.text()), frame.size());
+
+    let text = quote.get_text();
+    let areas = Layout::new(
+        Direction::Vertical,
+        [
+            Constraint::Percentage(10),
+            Constraint::Length(2),
+            Constraint::Length(2),
+            Constraint::Fill(1),
+            Constraint::Length(1),
+        ],
+    )
+    .split(frame.size());
+
+    frame.render_widget(
+        Paragraph::new(format!("## {}", quote.get_name()))
+            .alignment(Alignment::Center)
+            .style(theme.title()),
+        areas[1],
+    );
+
+    let words = text.split_whitespace().count();
+    let best = best.map_or(String::from("not raced yet"), |wpm| {
+        format!("best {wpm:.0} wpm")
+    });
+    frame.render_widget(
+        Paragraph::new(format!(
+            "{words} words | {} characters | {best}",
+            text.chars().count()
+        ))
+        .alignment(Alignment::Center),
+        areas[2],
+    );
+
+    frame.render_widget(
+        Paragraph::new(text).wrap(Wrap { trim: true }),
+        centered_width(70, areas[3]),
+    );
+
+    frame.render_widget(
+        Paragraph::new("(enter) race | (n) another quote | (esc) back")
+            .alignment(Alignment::Center),
+        areas[4],
+    );
+}
+
+pub fn browser(
+    frame: &mut Frame,
+    entries: &[&Entry],
+    filter: &Filter,
+    selected: usize,
+    theme: &Theme,
+) {
+    frame.render_widget(Block::new().style(theme.text()), frame.size());
+
+    let areas = Layout::new(
+        Direction::Vertical,
+        [
+            Constraint::Length(1),
+            Constraint::Length(4),
+            Constraint::Fill(1),
+            Constraint::Length(1),
+        ],
+    )
+    .split(frame.size());
+
+    let chosen = |value: Option<&str>| value.unwrap_or("any").to_string();
+    frame.render_widget(
+        Paragraph::new(format!(
+            "> {}\nlength {} | tag {} | language {}",
+            filter.query,
+            chosen(filter.length.map(|length| length.name())),
+            chosen(filter.tag),
+            chosen(filter.language),
+        ))
+        .block(Block::bordered().title("search")),
+        centered_width(86, areas[1]),
+    );
+
+    let header = ["quote", "words", "length", "tags", "best"]
+        .into_iter()
+        .collect::<Row>()
+        .style(theme.title());
+
+    let rows = entries.iter().map(|entry| {
+        Row::new([
+            String::from(entry.name),
+            entry.words().to_string(),
+            String::from(entry.length().name()),
+            entry.tags.join(", "),
+            entry
+                .best
+                .map_or_else(|| String::from("-"), |wpm| format!("{wpm:.0}")),
+        ])
+    });
+
+    let widths = [
+        Constraint::Fill(1),
+        Constraint::Length(5),
+        Constraint::Length(6),
+        Constraint::Length(18),
+        Constraint::Length(4),
+    ];
+
+    let mut state = TableState::default().with_selected(Some(selected));
+    frame.render_stateful_widget(
+        Table::new(rows, widths)
+            .header(header)
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+            .block(Block::bordered().title(format!("quotes ({})", entries.len()))),
+        centered_width(86, areas[2]),
+        &mut state,
+    );
+
+    frame.render_widget(
+        Paragraph::new(
+            "(enter) race | (tab) length | (^t) tag | (^l) language | (^r) random | (^n) unplayed | (^w) worst | (esc) back",
+        )
+        .alignment(Alignment::Center),
+        areas[3],
+    );
+}
+
+// The settings list above a live preview of the race screen.
+pub fn settings(
+    frame: &mut Frame,
+    rows: &[(&str, String)],
+    selected: usize,
+    preview: &RaceEngine,
+    status: &Status,
+    options: &Options,
+) {
+    let theme = &options.theme;
+    frame.render_widget(Block::new().style(theme.text()), frame.size());
+
+    let areas = Layout::new(
+        Direction::Vertical,
+        [
+            Constraint::Length(1),
+            Constraint::Length(rows.len() as u16 + 2),
+            Constraint::Fill(1),
+            Constraint::Length(1),
+        ],
+    )
+    .split(frame.size());
+
+    let rows = rows.iter().map(|(name, value)| {
+        Row::new([
+            Cell::from(*name).style(theme.title()),
+            Cell::from(value.as_str()),
+        ])
+    });
+
+    let mut state = TableState::default().with_selected(Some(selected));
+    frame.render_stateful_widget(
+        Table::new(rows, [Constraint::Length(16), Constraint::Fill(1)])
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+            .block(Block::bordered().title("settings")),
+        centered_width(50, areas[1]),
+        &mut state,
+    );
+
+    race(frame, areas[2], "preview", preview, status, options);
+
+    frame.render_widget(
+        Paragraph::new("(↑/↓) select | (←/→) change | (esc) back").alignment(Alignment::Center),
+        areas[3],
+    );
+}
+
+pub fn analysis(frame: &mut Frame, stats: &[LayoutStats], selected: usize, theme: &Theme) {
+    frame.render_widget(Block::new().style(theme.text()), frame.size());
+
+    let areas = Layout::new(
+        Direction::Vertical,
+        [
+            Constraint::Length(2),
+            Constraint::Length(stats.len() as u16 + 3),
+            Constraint::Length(2),
+            Constraint::Length(stats[selected].layout.height()),
+            Constraint::Fill(1),
+            Constraint::Length(1),
+        ],
+    )
+    .split(frame.size());
+
+    let header = ["layout", "sfb", "home", "alt", "roll"]
+        .into_iter()
+        .chain(FINGERS)
+        .collect::<Row>()
+        .style(theme.title());
+
+    let rows = stats.iter().enumerate().map(|(i, layout)| {
+        let mut cells = vec![
+            layout.layout.name.clone(),
+            format!("{:.1}%", layout.same_finger()),
+            format!("{:.1}%", layout.home_row()),
+            format!("{:.1}%", layout.alternation()),
+            format!("{:.1}%", layout.rolls()),
+        ];
+        cells.extend((0..FINGERS.len()).map(|finger| format!("{:.1}", layout.finger(finger))));
+
+        let row = Row::new(cells);
+        if i == selected {
+            row.style(Style::default().add_modifier(Modifier::REVERSED))
+        } else {
+            row
+        }
+    });
+
+    let widths = [Constraint::Length(8)]
+        .into_iter()
+        .chain([Constraint::Length(6); 4])
+        .chain([Constraint::Length(5); FINGERS.len()]);
+
+    frame.render_widget(
+        Table::new(rows, widths)
+            .header(header)
+            .block(Block::bordered().title("layout effort")),
+        centered_width(86, areas[1]),
+    );
+
+    let layout = &stats[selected];
+    frame.render_widget(
+        Paragraph::new(format!("## {}", layout.layout.name))
+            .alignment(Alignment::Center)
+            .style(theme.title()),
+        areas[2],
+    );
+
+    draw_heatmap(
+        frame,
+        &centered_width(layout.layout.width(), areas[3]),
+        &layout.layout,
+        &layout.keys,
+        theme,
+    );
+
+    frame.render_widget(
+        Paragraph::new("(←/→) layout | (q) quit").alignment(Alignment::Center),
+        areas[5],
+    );
+}
+
+fn centered_width(width: u16, r: Rect) -> Rect {
+    Layout::horizontal([
+        Constraint::Fill(1),
+        Constraint::Max(width),
+        Constraint::Fill(1),
+    ])
+    .split(r)[1]
+}
+
+fn centered_rect(percent_x: u16, height: u16, r: Rect) -> Rect {
     let popup_layout = Layout::vertical([
         Constraint::Fill(1),
-        Constraint::Length(percent_y),
+        Constraint::Length(height),
         Constraint::Fill(1),
     ])
     .split(r);
@@ -259,3 +949,133 @@ fn centered_rect(percent_x: u16, r: Rect) -> Rect {
     ])
     .split(popup_layout[1])[1]
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::Correction;
+    use crate::source::TextSource;
+    use crate::types::Geometry;
+    use crate::utils::get_keyboard_layout;
+    use ratatui::backend::TestBackend;
+    use std::time::{Duration, Instant};
+
+    const TEXT: &str = "the quick brown fox jumps over the lazy dog";
+    const WIDTHS: [u16; 9] = [1, 20, 39, 40, 59, 60, 80, 120, 200];
+    const HEIGHTS: [u16; 8] = [1, 5, 10, 20, 35, 36, 50, 80];
+
+    fn options(show_keyboard: bool) -> Options {
+        Options {
+            keyboard: get_keyboard_layout(None, Geometry::default(), false),
+            geometry: Geometry::default(),
+            theme: Theme::load(None),
+            theme_name: String::from("dark"),
+            hud: Hud::default(),
+            show_keyboard,
+            pacer: 0,
+            correction: Correction::default(),
+            split_interval: 1_000_000_000,
+            afk_timeout: 0,
+            source: TextSource::default(),
+            output: None,
+            once: false,
+        }
+    }
+
+    // An engine part-way through TEXT, with one mistake.
+    fn engine() -> (RaceEngine, Instant) {
+        let shown = Instant::now();
+        let mut engine = RaceEngine::new(TEXT, shown);
+        let mut at = shown;
+        for c in "the quixk".chars() {
+            at += Duration::from_millis(120);
+            engine.input(Some(c), at);
+        }
+        (engine, at)
+    }
+
+    fn finished() -> Race {
+        let shown = Instant::now();
+        let mut engine = RaceEngine::new(TEXT, shown);
+        let mut at = shown;
+        for c in TEXT.chars() {
+            at += Duration::from_millis(150);
+            engine.input(Some(c), at);
+        }
+        engine.finish(at)
+    }
+
+    fn render(width: u16, height: u16, draw: impl FnOnce(&mut Frame)) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal.draw(draw).unwrap();
+        let buffer = terminal.backend().buffer();
+        buffer.content().iter().map(|cell| cell.symbol()).collect()
+    }
+
+    #[test]
+    fn title_fits_every_size() {
+        let session = Session::default();
+        for width in WIDTHS {
+            for height in HEIGHTS {
+                let screen = render(width, height, |frame| {
+                    title(frame, &session, "user", "(q) quit", &Theme::load(None))
+                });
+                let compact = width < ART_WIDTH || height < ART_HEIGHT;
+                assert_eq!(screen.contains("$$"), !compact, "{width}x{height}");
+                if compact && width >= 10 && height >= 10 {
+                    assert!(screen.contains("Term Typer"), "{width}x{height}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn race_fits_every_size() {
+        let (engine, now) = engine();
+        let status = engine.status(now);
+        for show_keyboard in [true, false] {
+            let options = options(show_keyboard);
+            for width in WIDTHS {
+                for height in HEIGHTS {
+                    let screen = render(width, height, |frame| {
+                        race(frame, frame.size(), "quote", &engine, &status, &options)
+                    });
+                    let fits = width >= options.keyboard.width()
+                        && height >= options.keyboard.height() + MIN_TEXT_HEIGHT;
+                    assert_eq!(
+                        screen.contains('╭'),
+                        show_keyboard && fits,
+                        "{width}x{height}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn results_fit_every_size() {
+        let race = finished();
+        let keyboard = get_keyboard_layout(None, Geometry::default(), false);
+        let history = Vec::new();
+        for tab in ResultsTab::ALL {
+            let view = ResultsView {
+                tab,
+                ..ResultsView::default()
+            };
+            for width in WIDTHS {
+                for height in HEIGHTS {
+                    render(width, height, |frame| {
+                        results(
+                            frame,
+                            &race,
+                            view,
+                            &keyboard,
+                            &Best::new(&history, "user"),
+                            &Theme::load(None),
+                        )
+                    });
+                }
+            }
+        }
+    }
+}
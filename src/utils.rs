@@ -1,9 +1,15 @@
 use crate::types::*;
 
-use rand::Rng;
 use std::collections::HashMap;
+use std::env;
+use std::ops::Range;
+use std::path::PathBuf;
 
-pub fn get_keyboard_layout(layout: Option<&String>, shift: bool) -> KeyboardLayout {
+pub fn get_keyboard_layout(
+    layout: Option<&String>,
+    geometry: Geometry,
+    shift: bool,
+) -> KeyboardLayout {
     let default_layout = String::from("colemak");
 
     let layout = layout.unwrap_or(&default_layout);
@@ -65,21 +71,194 @@ pub fn get_keyboard_layout(layout: Option<&String>, shift: bool) -> KeyboardLayo
     layouts.insert(String::from("colemak"), colemak);
     layouts.insert(String::from("dvorak"), dvorak);
 
-    if !layouts.contains_key(layout) {
-        return KeyboardLayout {
-            rows: layouts.get("qwerty").unwrap().clone(),
-        };
-    }
+    let name = if layouts.contains_key(layout) {
+        layout.clone()
+    } else {
+        String::from("qwerty")
+    };
+    let rows = layouts.get(&name).unwrap().clone();
+    let keys = get_keys(&rows, &name, geometry);
+
+    KeyboardLayout { name, rows, keys }
+}
+
+fn get_keys(rows: &[[String; 10]; 3], name: &str, geometry: Geometry) -> Vec<Key> {
+    let key = |label: &str, legend: &str, x: u16, y: u16, width: u16| Key {
+        label: String::from(label),
+        legend: String::from(legend),
+        x,
+        y,
+        width,
+    };
 
-    return KeyboardLayout {
-        rows: layouts.get(layout).unwrap().clone(),
+    let row_stagger: [u16; 3] = match geometry {
+        Geometry::Ansi => [0, 1, 3],
+        Geometry::Iso => [1, 2, 4],
+        _ => [0, 0, 0],
+    };
+    let column_stagger: [u16; 10] = match geometry {
+        Geometry::Columnar | Geometry::Split => [2, 1, 0, 1, 1, 1, 1, 0, 1, 2],
+        _ => [0; 10],
+    };
+    let gap = |column: usize| -> u16 {
+        if geometry == Geometry::Split && column >= 5 {
+            8
+        } else {
+            0
+        }
     };
+
+    let mut keys = Vec::new();
+    for (row, labels) in rows.iter().enumerate() {
+        for (column, label) in labels.iter().enumerate() {
+            keys.push(key(
+                label,
+                label,
+                row_stagger[row] + column as u16 * 4 + gap(column),
+                row as u16 * KEY_HEIGHT + column_stagger[column],
+                4,
+            ));
+        }
+    }
+
+    match geometry {
+        Geometry::Ansi => keys.push(key(" ", name, 11, 9, 24)),
+        Geometry::Iso => {
+            keys.push(key("\\", "\\", 0, 6, 4));
+            keys.push(key(" ", name, 12, 9, 24));
+        }
+        Geometry::Ortho => keys.push(key(" ", name, 16, 9, 8)),
+        Geometry::Columnar => keys.push(key(" ", name, 16, 11, 8)),
+        Geometry::Split => {
+            keys.push(key("\t", "⇥", 12, 11, 4));
+            keys.push(key(" ", "␣", 16, 11, 4));
+            keys.push(key("\u{8}", "⌫", 28, 11, 4));
+            keys.push(key("\n", "⏎", 32, 11, 4));
+        }
+    }
+
+    keys
+}
+
+pub fn base_key(c: char) -> String {
+    match c {
+        ':' => String::from(";"),
+        '<' => String::from(","),
+        '>' => String::from("."),
+        '?' => String::from("/"),
+        '"' => String::from("'"),
+        _ => c.to_lowercase().to_string(),
+    }
+}
+
+pub fn wrap_words(text: &[char], width: usize) -> Vec<Range<usize>> {
+    let width = width.max(1);
+    let mut lines = Vec::new();
+    let mut start = 0;
+    let mut end = 0;
+
+    while end < text.len() {
+        let mut word_end = end;
+        while word_end < text.len() && text[word_end] != ' ' {
+            word_end += 1;
+        }
+        while word_end < text.len() && text[word_end] == ' ' {
+            word_end += 1;
+        }
+
+        if word_end - start > width && end > start {
+            lines.push(start..end);
+            start = end;
+        }
+        while word_end - start > width {
+            lines.push(start..start + width);
+            start += width;
+        }
+        end = word_end;
+    }
+
+    if start < end || lines.is_empty() {
+        lines.push(start..end);
+    }
+    lines
+}
+
+pub fn config_dir() -> Option<PathBuf> {
+    env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .map(|dir| dir.join("typing"))
+}
+
+// Formats a unix timestamp as a UTC date and time.
+pub fn date(timestamp: u64) -> String {
+    let days = (timestamp / 86400) as i64;
+    let seconds = timestamp % 86400;
+
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02} UTC",
+        seconds / 3600,
+        seconds % 3600 / 60
+    )
 }
 
-pub fn get_quote() -> Quote {
-    let mut rng = rand::thread_rng();
-    let idx: usize = rng.gen_range(0..QUOTES.len());
-    let (name, text) = QUOTES[idx];
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    Quote::new(String::from(name), String::from(text))
+    fn lines(text: &str, width: usize) -> Vec<String> {
+        let text: Vec<char> = text.chars().collect();
+        wrap_words(&text, width)
+            .into_iter()
+            .map(|line| text[line].iter().collect())
+            .collect()
+    }
+
+    #[test]
+    fn words_wrap_with_their_trailing_space() {
+        assert_eq!(
+            lines("the quick brown fox", 10),
+            ["the quick ", "brown fox"]
+        );
+        assert_eq!(lines("the quick brown fox", 100), ["the quick brown fox"]);
+        assert_eq!(lines("", 10), [""]);
+    }
+
+    #[test]
+    fn long_words_are_split() {
+        assert_eq!(lines("a abcdefgh b", 4), ["a ", "abcd", "efgh", " b"]);
+        assert_eq!(lines("abc", 0), ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn wrapping_covers_the_text_in_order() {
+        let text: Vec<char> = "some words, then a verylongwordthatwontfit and more"
+            .chars()
+            .collect();
+        for width in 1..60 {
+            let lines = wrap_words(&text, width);
+            assert_eq!(lines.first().map(|line| line.start), Some(0));
+            assert_eq!(lines.last().map(|line| line.end), Some(text.len()));
+            assert!(lines.windows(2).all(|pair| pair[0].end == pair[1].start));
+            assert!(lines.iter().all(|line| line.len() <= width));
+        }
+    }
+
+    #[test]
+    fn dates_are_utc() {
+        assert_eq!(date(0), "1970-01-01 00:00 UTC");
+        assert_eq!(date(951_782_400), "2000-02-29 00:00 UTC");
+        assert_eq!(date(1_792_405_073), "2026-10-19 10:17 UTC");
+    }
 }
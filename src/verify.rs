@@ -0,0 +1,240 @@
+use crate::anticheat;
+use crate::stats::Record;
+use crate::utils::config_dir;
+
+use hmac::{Hmac, KeyInit, Mac};
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io;
+
+type HmacSha256 = Hmac<Sha256>;
+
+const KEY_FILE: &str = "key";
+const TOLERANCE: f64 = 0.5;
+
+// Loads the per-install signing key, creating one on first use.
+pub fn install_key() -> io::Result<Vec<u8>> {
+    let dir = config_dir()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no config directory"))?;
+    let path = dir.join(KEY_FILE);
+
+    match fs::read_to_string(&path) {
+        Ok(key) => from_hex(key.trim())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "malformed signing key")),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => {
+            let mut key = vec![0; 32];
+            rand::thread_rng().fill_bytes(&mut key);
+            fs::create_dir_all(&dir)?;
+            fs::write(&path, to_hex(&key))?;
+            Ok(key)
+        }
+        Err(error) => Err(error),
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+fn from_hex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+// Each link hashes the previous link together with one keystroke, so no keystroke can be
+// edited, dropped or reordered without changing the final link.
+pub fn chain(record: &Record) -> String {
+    let mut link = to_hex(&Sha256::digest(format!(
+        "{}\n{}\n{}\n{}\n",
+        record.user, record.quote, record.layout, record.timestamp
+    )));
+
+    for keypress in record.keystrokes.iter() {
+        link = to_hex(&Sha256::digest(format!(
+            "{link}\n{} {} {:?} {}\n",
+            keypress.time, keypress.position, keypress.typed, keypress.correct
+        )));
+    }
+
+    link
+}
+
+fn payload(record: &Record) -> String {
+    format!(
+        "{}\n{}\n{}\n{}\n{:.4}\n{:.4}\n{:.4}\n{:.4}\n{:.4}\n{:?}\n{:.4}\n{:.4}\n{:?}\n{}\n",
+        record.user,
+        record.quote,
+        record.layout,
+        record.timestamp,
+        record.wpm,
+        record.raw,
+        record.accuracy,
+        record.consistency,
+        record.burst,
+        record.slowest_word,
+        record.reaction,
+        record.time,
+        record.flags,
+        record.chain,
+    )
+}
+
+fn mac(key: &[u8], record: &Record) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(key).expect("hmac accepts any key length");
+    mac.update(payload(record).as_bytes());
+    mac
+}
+
+pub fn sign(record: &mut Record, key: &[u8]) {
+    record.chain = chain(record);
+    record.signature = to_hex(&mac(key, record).finalize().into_bytes());
+}
+
+fn close(computed: f64, reported: f64) -> bool {
+    (computed - reported).abs() <= TOLERANCE
+}
+
+// Everything wrong with a record; empty when it checks out.
+pub fn problems(record: &Record, key: &[u8]) -> Vec<String> {
+    let mut problems = Vec::new();
+
+    if record.signature.is_empty() {
+        problems.push(String::from("unsigned"));
+    } else {
+        let valid = from_hex(&record.signature)
+            .is_some_and(|signature| mac(key, record).verify_slice(&signature).is_ok());
+        if !valid {
+            problems.push(String::from("signature does not match this install's key"));
+        }
+    }
+
+    if record.chain != chain(record) {
+        problems.push(String::from("keystroke log does not match its hash chain"));
+    }
+
+    let hits = record.keystrokes.iter().filter(|k| k.correct).count() as f64;
+    let misses = record.keystrokes.len() as f64 - hits;
+    let minutes = record.time / 60.0;
+    let wpm = hits / 5.0 / minutes;
+    let raw = (hits + misses) / 5.0 / minutes;
+    let accuracy = hits / (hits + misses) * 100.0;
+
+    if !close(wpm, record.wpm) {
+        problems.push(format!(
+            "wpm {:.0} does not match keystrokes ({wpm:.0})",
+            record.wpm
+        ));
+    }
+    if !close(raw, record.raw) {
+        problems.push(format!(
+            "raw {:.0} does not match keystrokes ({raw:.0})",
+            record.raw
+        ));
+    }
+    if !close(accuracy, record.accuracy) {
+        problems.push(format!(
+            "accuracy {:.2}% does not match keystrokes ({accuracy:.2}%)",
+            record.accuracy
+        ));
+    }
+
+    if record
+        .keystrokes
+        .windows(2)
+        .any(|pair| pair[1].time < pair[0].time)
+    {
+        problems.push(String::from("keystrokes out of order"));
+    }
+    if record
+        .keystrokes
+        .last()
+        .is_some_and(|last| last.time as f64 / 1e9 > record.time + TOLERANCE)
+    {
+        problems.push(String::from("keystrokes after the race ended"));
+    }
+    for flag in anticheat::timing_flags(&record.keystrokes) {
+        if !record.flags.contains(&flag) {
+            problems.push(format!("unreported {}", flag.description()));
+        }
+    }
+
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::RaceEngine;
+    use std::time::{Duration, Instant};
+
+    const KEY: &[u8] = b"test key";
+
+    fn record(key: Option<&[u8]>) -> Record {
+        let start = Instant::now();
+        let mut engine = RaceEngine::new("a quick test", start).with_afk_timeout(0);
+        let mut time = start;
+        for (i, c) in "a quixk test".chars().enumerate() {
+            time += Duration::from_millis(150 + i as u64 * 31 % 80);
+            engine.input(Some(c), time);
+        }
+        engine.input(Some('c'), time);
+        engine.input(Some('k'), time + Duration::from_millis(120));
+
+        Record::new(
+            &engine.finish(time + Duration::from_millis(120)),
+            "user",
+            "quote",
+            "qwerty",
+            key,
+        )
+    }
+
+    #[test]
+    fn signed_records_check_out() {
+        assert!(problems(&record(Some(KEY)), KEY).is_empty());
+    }
+
+    #[test]
+    fn other_keys_do_not_match() {
+        assert_eq!(
+            problems(&record(Some(KEY)), b"other key"),
+            ["signature does not match this install's key"]
+        );
+    }
+
+    #[test]
+    fn unsigned_records_are_reported() {
+        let mut record = record(Some(KEY));
+        record.signature.clear();
+        assert_eq!(problems(&record, KEY), ["unsigned"]);
+    }
+
+    #[test]
+    fn records_made_without_a_key_are_unsigned_but_chained() {
+        assert_eq!(problems(&record(None), KEY), ["unsigned"]);
+    }
+
+    #[test]
+    fn edited_scores_are_caught() {
+        let mut record = record(Some(KEY));
+        record.wpm *= 2.0;
+        let problems = problems(&record, KEY);
+        assert_eq!(problems[0], "signature does not match this install's key");
+        assert!(problems[1].starts_with("wpm "));
+    }
+
+    #[test]
+    fn edited_keystrokes_break_the_chain() {
+        let mut record = record(Some(KEY));
+        record.keystrokes.swap(0, 1);
+        let problems = problems(&record, KEY);
+        assert!(problems.contains(&String::from("keystroke log does not match its hash chain")));
+        assert!(problems.contains(&String::from("keystrokes out of order")));
+    }
+}